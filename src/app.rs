@@ -1,4 +1,10 @@
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 use std::io::{self, Write};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use termion::cursor::Goto;
 use termion::event::Key;
@@ -10,62 +16,221 @@ use tui::layout::{Alignment, Constraint, Corner, Direction, Layout};
 use tui::style::{Color, Modifier, Style};
 use tui::widgets::{Block, Borders, List as TuiList, SelectableList, Paragraph, Text, Widget};
 use tui::Terminal;
-use unicode_width::UnicodeWidthStr;
-
-use crate::util::event::{Event, Events};
-use crate::list::{List, Item};
-use crate::options::{Options, PageOptions};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::arena::{Arena, Index};
+use crate::change::{Change, Path};
+use crate::history::{History, Snapshot};
+use crate::id;
+use crate::util::event::{Config, Event, Events};
+use crate::util::tty::StdoutToTty;
+use crate::list::{List, Item, Position};
+use crate::notification::{Level, Notification};
+use crate::search::{self, Filter, SearchResult};
+use crate::options::{Options, PageOptions, ViewMode};
+
+pub type SaveHandler = Arc<dyn Fn(crate::List) -> Option<Notification> + Send + Sync>;
+pub type ChangeHandler = Box<dyn Fn(Change)>;
 
 type Term = Terminal<TermionBackend<AlternateScreen<MouseTerminal<termion::raw::RawTerminal<std::io::Stdout>>>>>;
 
 pub struct App{
-    pub lists: Vec<List>,
-    current: usize,
+    pub lists: Arena<List>,
+    pub root: Index,
+    current: Index,
     pub options: Options,
     depth: usize,
-    on_save: Box<dyn Fn(Vec<List>) -> Option<String>>,
+    on_save: SaveHandler,
+    on_change: ChangeHandler,
+    id_generator: id::Generator,
     running: bool,
-    notification: Option<String>,
+    notification: Option<Notification>,
+    notified_at: Instant,
+    // item cut or copied from the tree, its nested lists are kept in the
+    // arena, detached from the root, until pasted
+    clipboard: Option<Item>,
+    history: History,
+    // changes are numbered as they are made, undoing one restores the
+    // number it replaced so the tree is clean again when back at the save
+    revision: u64,
+    revisions: u64,
+    saved_revision: u64,
+    saved_at: Instant,
+    // quitting was asked for with unsaved changes, waiting on what to do
+    confirming_exit: bool,
+    // saves run on their own thread and report back through the event
+    // channel. This is the revision being saved, while one is running
+    saving: Option<u64>,
+    // another save was asked for while one was running
+    save_pending: bool,
+    exit_after_save: bool,
+    events: Option<Sender<Event<Key>>>,
+    // narrows the menu while typing after `/`
+    filter: Option<Filter>,
+    // rows that fit in the menu pane as last drawn, for paging through it
+    menu_height: Cell<usize>,
 }
 
 impl App{
     pub fn new(root_list: List) -> App {
+        let mut lists = Arena::new();
+        let root = lists.insert(root_list);
+
         App{
-            lists: vec!(root_list),
-            current: 0,
+            lists,
+            root,
+            current: root,
             options: Options::new(),
             depth: 0,
-            on_save: Box::new(|_: Vec<List>| None),
+            on_save: Arc::new(|_: crate::List| None),
+            on_change: Box::new(|_: Change| {}),
+            id_generator: id::name(),
             running: false,
             notification: None,
+            notified_at: Instant::now(),
+            clipboard: None,
+            history: History::new(100),
+            revision: 0,
+            revisions: 0,
+            saved_revision: 0,
+            saved_at: Instant::now(),
+            confirming_exit: false,
+            saving: None,
+            save_pending: false,
+            exit_after_save: false,
+            events: None,
+            filter: None,
+            menu_height: Cell::new(0),
         }
     }
 
-    pub fn register_save_handler(&mut self, on_save: Box<dyn Fn(Vec<List>) -> Option<String>>) {
+    pub fn register_save_handler(&mut self, on_save: SaveHandler) {
         self.on_save = on_save
     }
 
+    pub fn register_change_handler(&mut self, on_change: ChangeHandler) {
+        self.on_change = on_change
+    }
+
+    pub fn register_id_generator(&mut self, id_generator: id::Generator) {
+        self.id_generator = id_generator
+    }
+
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history.set_depth(depth);
+    }
+
     pub fn save(&mut self) {
-        self.notification = (self.on_save)(self.lists.clone());
+        if self.saving.is_some() {
+            self.save_pending = true;
+            return;
+        }
+
+        let events = match &self.events {
+            Some(events) => events.clone(),
+            None => return,
+        };
+        let on_save = Arc::clone(&self.on_save);
+        let list = self.user_list();
+
+        self.saving = Some(self.revision);
+        self.saved_at = Instant::now();
+
+        thread::spawn(move || {
+            let notification = on_save(list);
+            events.send(Event::Saved(notification)).ok();
+        });
+    }
+
+    fn finish_save(&mut self, notification: Option<Notification>) {
+        let failed = notification.as_ref().is_some_and(Notification::is_error);
+
+        // a failed save leaves the changes unsaved
+        if let Some(revision) = self.saving.take() {
+            if !failed {
+                self.saved_revision = revision;
+            }
+        }
+        self.notify(notification);
+
+        if self.save_pending {
+            self.save_pending = false;
+
+            if self.is_dirty() {
+                self.save();
+                return;
+            }
+        }
+
+        if self.exit_after_save {
+            self.exit_after_save = false;
+
+            if !self.is_dirty() {
+                self.stop();
+            } else if !failed {
+                self.confirming_exit = true;
+            }
+        }
+    }
+
+    // the tree as a public List, to be handed off to the save handler
+    pub fn user_list(&self) -> crate::List {
+        let root = &self.lists[self.root];
+        crate::List::new(root.name.clone(), crate::items_to_user(&self.lists, &root.items))
+    }
+
+    fn notify(&mut self, notification: Option<Notification>) {
+        self.notification = notification;
+        self.notified_at = Instant::now();
     }
 
     pub fn stop(&mut self) {
         self.running = false;
     }
 
-    pub fn add_list(&mut self, name: String) -> usize {
+    pub fn is_dirty(&self) -> bool {
+        self.revision != self.saved_revision
+    }
+
+    // stops right away when everything is saved, otherwise asks first
+    fn quit(&mut self) {
+        if self.saving.is_some() {
+            self.exit_after_save = true;
+        } else if self.is_dirty() {
+            self.confirming_exit = true;
+        } else {
+            self.stop();
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.notification.is_some() && self.notified_at.elapsed() >= NOTIFICATION_TIMEOUT {
+            self.notification = None;
+        }
+
+        if let Some(interval) = self.options.autosave {
+            if self.is_dirty() && self.saving.is_none() && self.saved_at.elapsed() >= interval {
+                self.save();
+            }
+        }
+    }
+
+    pub fn add_list(&mut self, name: String) -> Index {
         let mut list = List::new(name);
         list.previous = Some(self.current);
-        self.lists.push(list);
 
-        return self.lists.len() - 1;
+        return self.lists.insert(list);
     }
 
     fn get_current_page_options(&self) -> PageOptions {
-        if self.options.page_options.len() > self.depth {
-            self.options.page_options[self.depth].clone()
+        self.get_page_options(self.depth)
+    }
+
+    fn get_page_options(&self, depth: usize) -> PageOptions {
+        if self.options.page_options.len() > depth {
+            self.options.page_options[depth].clone()
         } else {
-            PageOptions::new(format!("{}", self.depth))
+            PageOptions::new(format!("{}", depth))
         }
     }
 
@@ -92,7 +257,185 @@ impl App{
         }
     }
 
+    fn generate_id(&self, name: &str) -> String {
+        self.generate_id_avoiding(name, &HashSet::new())
+    }
+
+    // like `generate_id`, also avoiding ids already given to items that
+    // aren't in the tree yet
+    fn generate_id_avoiding(&self, name: &str, reserved: &HashSet<String>) -> String {
+        let taken: HashSet<&str> = self.lists.values()
+            .flat_map(|list| list.items.iter())
+            .chain(self.clipboard.iter())
+            .map(|item| item.id.as_str())
+            .chain(reserved.iter().map(|id| id.as_str()))
+            .collect();
+
+        let id = (self.id_generator)(name);
+        if !taken.contains(id.as_str()) {
+            return id;
+        }
+
+        let mut suffix = 2;
+        while taken.contains(format!("{}-{}", id, suffix).as_str()) {
+            suffix += 1;
+        }
+
+        format!("{}-{}", id, suffix)
+    }
+
+    fn snapshot(&self, description: String) -> Snapshot {
+        Snapshot{
+            description,
+            lists: self.lists.clone(),
+            root: self.root,
+            current: self.current,
+            depth: self.depth,
+            clipboard: self.clipboard.clone(),
+            revision: self.revision,
+            changes: Vec::new(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.filter = None;
+        self.lists = snapshot.lists;
+        // compacting may have moved the root since the snapshot was taken
+        self.root = snapshot.root;
+        self.current = snapshot.current;
+        self.depth = snapshot.depth;
+        self.clipboard = snapshot.clipboard;
+        self.revision = snapshot.revision;
+    }
+
+    // records the current state so the change about to be made can be undone
+    fn checkpoint(&mut self, description: String) {
+        let snapshot = self.snapshot(description);
+        self.history.record(snapshot);
+
+        self.revisions += 1;
+        self.revision = self.revisions;
+    }
+
+    // reports a change to the tree, keeping its inverse for undo
+    fn emit(&mut self, change: Change, inverse: Change) {
+        (self.on_change)(change.clone());
+        self.history.record_change(change, inverse);
+    }
+
+    fn list_path(&self, list_index: Index) -> Path {
+        let mut path = Vec::new();
+        let mut index = list_index;
+
+        while let Some(parent_index) = self.lists[index].previous {
+            let position = self.lists[parent_index].items.iter()
+                .position(|item| item.list_index == Some(index));
+
+            match position {
+                Some(position) => path.push(position),
+                None => break,
+            }
+            index = parent_index;
+        }
+
+        path.reverse();
+        path
+    }
+
+    fn item_path(&self, list_index: Index, position: usize) -> Path {
+        let mut path = self.list_path(list_index);
+        path.push(position);
+        path
+    }
+
+    fn selected_item_path(&self) -> Option<Path> {
+        self.lists[self.current].selected.map(|selected| self.item_path(self.current, selected))
+    }
+
+    fn to_user_item(&self, item: &Item) -> crate::Item {
+        crate::item_to_user(&self.lists, item)
+    }
+
+    fn emit_added(&mut self, path: Path, item: &Item) {
+        let item = self.to_user_item(item);
+        self.emit(
+            Change::ItemAdded{path: path.clone(), item: item.clone()},
+            Change::ItemRemoved{path, item},
+        );
+    }
+
+    fn emit_removed(&mut self, path: Path, item: &Item) {
+        let item = self.to_user_item(item);
+        self.emit(
+            Change::ItemRemoved{path: path.clone(), item: item.clone()},
+            Change::ItemAdded{path, item},
+        );
+    }
+
+    // reports the selected item's done and metadata after they changed from
+    // those of `previous`
+    fn emit_selected_updated(&mut self, previous: &Item) {
+        if let (Some(path), Some(item)) = (self.selected_item_path(), self.get_selected_item()) {
+            let change = Change::ItemUpdated{
+                path: path.clone(),
+                done: item.done,
+                metadata: item.metadata.clone(),
+            };
+            let inverse = Change::ItemUpdated{
+                path,
+                done: previous.done,
+                metadata: previous.metadata.clone(),
+            };
+            self.emit(change, inverse);
+        }
+    }
+
+    fn describe_selected_item(&self, action: &str) -> String {
+        match self.get_selected_item() {
+            Some(item) => format!("{} \"{}\"", action, item.name),
+            None => action.to_string(),
+        }
+    }
+
+    fn undo(&mut self) {
+        let current = self.snapshot(String::new());
+
+        let notification = match self.history.undo(current) {
+            Some(previous) => {
+                let description = previous.description.clone();
+                for (_, inverse) in previous.changes.iter().rev() {
+                    (self.on_change)(inverse.clone());
+                }
+                self.restore(previous);
+                Notification::info(format!("Undid {}", description))
+            }
+            None => Notification::warning(String::from("Nothing to undo")),
+        };
+        self.notify(Some(notification));
+    }
+
+    fn redo(&mut self) {
+        let current = self.snapshot(String::new());
+
+        let notification = match self.history.redo(current) {
+            Some(next) => {
+                let description = next.description.clone();
+                for (change, _) in next.changes.iter() {
+                    (self.on_change)(change.clone());
+                }
+                self.restore(next);
+                Notification::info(format!("Redid {}", description))
+            }
+            None => Notification::warning(String::from("Nothing to redo")),
+        };
+        self.notify(Some(notification));
+    }
+
     fn add_list_item(&mut self, name: String, id: String) {
+        if self.get_selected_item().is_some() {
+            self.checkpoint(format!("add \"{}\"", name));
+        }
+
         match self.lists[self.current].get_selected_item() {
             Some(selected_item) => {
                 match selected_item.list_index {
@@ -111,6 +454,12 @@ impl App{
                         match selected_item.list_index {
                             Some(list_index) => {
                                 self.lists[list_index].items.push(item);
+                                self.lists[list_index].expanded = true;
+
+                                let position = self.lists[list_index].items.len() - 1;
+                                let path = self.item_path(list_index, position);
+                                let item = self.lists[list_index].items[position].clone();
+                                self.emit_added(path, &item);
                             }
                             None => {}
                         }
@@ -122,6 +471,597 @@ impl App{
         }
     }
 
+    fn add_sibling_item(&mut self, name: String, id: String, position: Position) {
+        if !self.get_current_page_options().disable_add {
+            self.checkpoint(format!("add \"{}\"", name));
+
+            let item = Item::new(id, name);
+            self.lists[self.current].insert_item(item.clone(), position);
+
+            if let Some(path) = self.selected_item_path() {
+                self.emit_added(path, &item);
+            }
+        }
+    }
+
+    fn rename_selected_item(&mut self, name: String) {
+        if !self.get_current_page_options().disable_edit {
+            let (path, previous_name) = match (self.selected_item_path(), self.get_selected_item()) {
+                (Some(path), Some(item)) => (path, item.name.clone()),
+                _ => return,
+            };
+
+            let description = format!("{} to \"{}\"", self.describe_selected_item("rename"), name);
+            self.checkpoint(description);
+            self.lists[self.current].set_selected_item_name(name.clone());
+
+            self.emit(
+                Change::ItemRenamed{path: path.clone(), name},
+                Change::ItemRenamed{path, name: previous_name},
+            );
+        }
+    }
+
+    fn toggle_selected_item_done(&mut self) {
+        if !self.get_current_page_options().disable_edit {
+            let previous = match self.get_selected_item() {
+                Some(item) => item.clone(),
+                None => return,
+            };
+            let description = if previous.done {
+                self.describe_selected_item("uncheck")
+            } else {
+                self.describe_selected_item("check")
+            };
+
+            self.checkpoint(description);
+            self.lists[self.current].toggle_selected_item_done();
+            self.emit_selected_updated(&previous);
+        }
+    }
+
+    // counts the done and total items at the bottom of a nested list
+    fn get_list_progress(&self, list_index: Index) -> (usize, usize) {
+        self.lists[list_index].items.iter().fold((0, 0), |(done, total), item| {
+            match item.list_index {
+                Some(nested_index) if !self.lists[nested_index].items.is_empty() => {
+                    let (nested_done, nested_total) = self.get_list_progress(nested_index);
+                    (done + nested_done, total + nested_total)
+                }
+                _ => (done + item.done as usize, total + 1),
+            }
+        })
+    }
+
+    fn get_menu_label(&self, item: &Item) -> String {
+        match self.get_menu_progress(item) {
+            Some(progress) => format!("{} {} {}", get_menu_checkbox(item), item.name, progress),
+            None => format!("{} {}", get_menu_checkbox(item), item.name),
+        }
+    }
+
+    fn get_menu_progress(&self, item: &Item) -> Option<String> {
+        match item.list_index {
+            Some(list_index) if !self.lists[list_index].items.is_empty() => {
+                let (done, total) = self.get_list_progress(list_index);
+                Some(format!("{}/{}", done, total))
+            }
+            _ => None,
+        }
+    }
+
+    // the filtered menu, one line per match with the matched characters of
+    // each name highlighted, laid out like the SelectableList it replaces
+    fn get_filter_menu_lines(&self, filter: &Filter, style: Style, highlight_style: Style) -> Vec<Text<'static>> {
+        let mut lines = Vec::new();
+
+        for (position, (index, matched)) in filter.matches.iter().enumerate() {
+            let item = &self.lists[self.current].items[*index];
+            let line_style = if filter.selected == Some(position) { highlight_style } else { style };
+            let match_style = line_style.fg(Color::Yellow);
+
+            let symbol = if filter.selected == Some(position) { ">" } else { " " };
+            lines.push(Text::styled(format!("{} {} ", symbol, get_menu_checkbox(item)), line_style));
+
+            lines.extend(highlight_matches(&item.name, matched, line_style, match_style));
+
+            if let Some(progress) = self.get_menu_progress(item) {
+                lines.push(Text::styled(format!(" {}", progress), line_style));
+            }
+            lines.push(Text::raw("\n"));
+        }
+
+        lines
+    }
+
+    // the names of the lists from the root down to the current one
+    fn get_breadcrumb(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut list_index = Some(self.current);
+
+        while let Some(index) = list_index {
+            names.push(self.lists[index].name.clone());
+            list_index = self.lists[index].previous;
+        }
+
+        names.reverse();
+        names
+    }
+
+    // selects each item on the way down to a search result and opens the
+    // list it is in. Like `open_selected_item_list`, lists aren't entered
+    // from pages with editing disabled, leaving the item that leads to the
+    // result selected instead
+    fn jump_to(&mut self, trail: &[(Index, usize)]) {
+        if trail.is_empty() {
+            return;
+        }
+
+        let depth = (0..trail.len() - 1)
+            .find(|depth| self.get_page_options(*depth).disable_edit)
+            .unwrap_or(trail.len() - 1);
+
+        for (list_index, position) in trail[..=depth].iter() {
+            self.lists[*list_index].set_selected_item_index(Some(*position));
+        }
+
+        self.current = trail[depth].0;
+        self.depth = depth;
+        self.filter = None;
+        self.notification = None;
+    }
+
+    fn is_outline(&self) -> bool {
+        self.options.view_mode == ViewMode::Outline
+    }
+
+    fn toggle_view_mode(&mut self) {
+        self.options.view_mode = match self.options.view_mode {
+            ViewMode::DrillDown => ViewMode::Outline,
+            ViewMode::Outline => ViewMode::DrillDown,
+        };
+        self.filter = None;
+    }
+
+    // every item shown in the outline view from top to bottom, as the list
+    // it is in and its position there, along with the guides drawn before it
+    fn get_outline_rows(&self) -> Vec<(Index, usize, String)> {
+        let mut rows = Vec::new();
+        self.push_outline_rows(self.root, "", &mut rows);
+        rows
+    }
+
+    fn push_outline_rows(&self, list_index: Index, guides: &str, rows: &mut Vec<(Index, usize, String)>) {
+        let items = &self.lists[list_index].items;
+
+        for (position, item) in items.iter().enumerate() {
+            let last = position + 1 == items.len();
+            let nested_index = item.list_index.filter(|index| !self.lists[*index].items.is_empty());
+            let expanded = nested_index.is_some_and(|index| self.lists[index].expanded);
+
+            let branch = if last { "└─" } else { "├─" };
+            let marker = match nested_index {
+                Some(_) if expanded => "▾",
+                Some(_) => "▸",
+                None => "─",
+            };
+            rows.push((list_index, position, format!("{}{}{} ", guides, branch, marker)));
+
+            if let (Some(index), true) = (nested_index, expanded) {
+                let nested_guides = format!("{}{}", guides, if last { "  " } else { "│ " });
+                self.push_outline_rows(index, &nested_guides, rows);
+            }
+        }
+    }
+
+    fn get_outline_cursor_row(&self, rows: &[(Index, usize, String)]) -> Option<usize> {
+        let selected = self.lists[self.current].selected?;
+        rows.iter().position(|(list_index, position, _)| *list_index == self.current && *position == selected)
+    }
+
+    // selects an item anywhere in the tree, along with the items above it so
+    // that leaving its list lands on its parent
+    fn set_outline_cursor(&mut self, list_index: Index, position: usize) {
+        self.lists[list_index].set_selected_item_index(Some(position));
+        self.current = list_index;
+        self.depth = 0;
+
+        let mut index = list_index;
+        while let Some(parent_index) = self.lists[index].previous {
+            let parent_position = self.lists[parent_index].items.iter()
+                .position(|item| item.list_index == Some(index));
+            if parent_position.is_some() {
+                self.lists[parent_index].set_selected_item_index(parent_position);
+            }
+
+            self.depth += 1;
+            index = parent_index;
+        }
+    }
+
+    fn move_outline_cursor(&mut self, down: bool) {
+        let rows = self.get_outline_rows();
+        if rows.is_empty() {
+            return;
+        }
+
+        let next = match (self.get_outline_cursor_row(&rows), down) {
+            (Some(row), true) => (row + 1) % rows.len(),
+            (Some(row), false) => row.checked_sub(1).unwrap_or(rows.len() - 1),
+            (None, _) => 0,
+        };
+
+        let (list_index, position, _) = rows[next];
+        self.set_outline_cursor(list_index, position);
+    }
+
+    // moves the cursor to another row of the menu, given the row it is on and
+    // how many rows there are. Stops at the first and last rows
+    fn select_menu_row<F: Fn(usize, usize) -> usize>(&mut self, to: F) {
+        if self.is_outline() {
+            let rows = self.get_outline_rows();
+            if rows.is_empty() {
+                return;
+            }
+
+            let row = self.get_outline_cursor_row(&rows).unwrap_or(0);
+            let (list_index, position, _) = rows[to(row, rows.len()).min(rows.len() - 1)];
+            self.set_outline_cursor(list_index, position);
+        } else {
+            let list = &mut self.lists[self.current];
+            if list.items.is_empty() {
+                return;
+            }
+
+            let row = list.selected.unwrap_or(0);
+            let index = to(row, list.items.len()).min(list.items.len() - 1);
+            list.set_selected_item_index(Some(index));
+        }
+    }
+
+    // shows the selected item's nested list, or steps into it when it is
+    // already shown
+    fn expand_selected_item(&mut self) {
+        if let Some(list_index) = self.get_selected_item().and_then(|item| item.list_index) {
+            if self.lists[list_index].items.is_empty() {
+                return;
+            }
+
+            if self.lists[list_index].expanded {
+                self.set_outline_cursor(list_index, 0);
+            } else {
+                self.lists[list_index].expanded = true;
+            }
+        }
+    }
+
+    // hides the selected item's nested list, or steps out to the parent item
+    // when there is nothing to hide
+    fn collapse_selected_item(&mut self) {
+        match self.get_selected_item().and_then(|item| item.list_index) {
+            Some(list_index) if self.lists[list_index].expanded && !self.lists[list_index].items.is_empty() => {
+                self.lists[list_index].expanded = false;
+            }
+            _ => self.close_current_list(),
+        }
+    }
+
+    // expands the lists above the selection so it can be seen in the outline
+    fn reveal_current_list(&mut self) {
+        let mut index = self.current;
+
+        while let Some(parent_index) = self.lists[index].previous {
+            self.lists[index].expanded = true;
+            index = parent_index;
+        }
+    }
+
+    fn start_filter(&mut self) {
+        let list = &self.lists[self.current];
+        self.filter = Some(Filter::new(&list.items, list.selected));
+    }
+
+    fn handle_filter_input(&mut self, input: Key) {
+        let mut filter = match self.filter.take() {
+            Some(filter) => filter,
+            None => return,
+        };
+
+        match input {
+            Key::Ctrl('c') => {
+                self.quit();
+                return;
+            }
+            Key::Esc => {
+                self.lists[self.current].set_selected_item_index(filter.previous_selected);
+                return;
+            }
+            Key::Char('\n') => {
+                if filter.selected.is_none() {
+                    self.lists[self.current].set_selected_item_index(filter.previous_selected);
+                }
+                return;
+            }
+            Key::Left => {
+                self.close_current_list();
+                return;
+            }
+            Key::Right => {
+                self.open_selected_item_list();
+                return;
+            }
+            Key::Up => {
+                filter.select_previous();
+            }
+            Key::Down => {
+                filter.select_next();
+            }
+            Key::Backspace => {
+                filter.query.pop();
+                filter.update(&self.lists[self.current].items);
+            }
+            Key::Char(c) => {
+                filter.query.push(c);
+                filter.update(&self.lists[self.current].items);
+            }
+            _ => {}
+        }
+
+        // the selected match is selected in the list too, so the other panes
+        // and actions follow it
+        if let Some(index) = filter.get_selected_item_index() {
+            self.lists[self.current].set_selected_item_index(Some(index));
+        }
+        self.filter = Some(filter);
+    }
+
+    fn set_selected_item_metadata(&mut self, key: String, value: String) {
+        if !self.get_current_page_options().disable_edit {
+            let previous = match self.get_selected_item() {
+                Some(item) => item.clone(),
+                None => return,
+            };
+
+            let description = format!("{} on {}", key, self.describe_selected_item("set"));
+            self.checkpoint(description);
+            self.lists[self.current].set_selected_item_metadata(key, value);
+            self.emit_selected_updated(&previous);
+        }
+    }
+
+    fn move_selected_item(&mut self, to: fn(usize, usize) -> usize) {
+        if !self.get_current_page_options().disable_edit {
+            let list = &self.lists[self.current];
+
+            if let Some(selected) = list.selected {
+                let index = to(selected, list.items.len()).min(list.items.len() - 1);
+
+                if index != selected {
+                    let from = self.item_path(self.current, selected);
+                    let to = self.item_path(self.current, index);
+
+                    let description = self.describe_selected_item("move");
+                    self.checkpoint(description);
+                    self.lists[self.current].move_selected_item(index);
+
+                    self.emit(
+                        Change::ItemMoved{from: from.clone(), to: to.clone()},
+                        Change::ItemMoved{from: to, to: from},
+                    );
+                }
+            }
+        }
+    }
+
+    fn cut_selected_item(&mut self) {
+        if !self.get_current_page_options().disable_delete && self.get_selected_item().is_some() {
+            let description = self.describe_selected_item("cut");
+            self.checkpoint(description);
+
+            let path = self.selected_item_path();
+            if let Some(item) = self.lists[self.current].take_selected_item() {
+                if let Some(path) = path {
+                    self.emit_removed(path, &item);
+                }
+                self.set_clipboard(item);
+
+                if self.lists[self.current].items.is_empty() {
+                    self.close_current_list();
+                }
+            }
+        }
+    }
+
+    fn copy_selected_item(&mut self) {
+        if let Some(item) = self.get_selected_item().cloned() {
+            let copy = self.clone_item(&item, self.current);
+            self.set_clipboard(copy);
+        }
+    }
+
+    fn set_clipboard(&mut self, item: Item) {
+        if let Some(previous) = self.clipboard.take() {
+            self.free_item_lists(&previous);
+        }
+
+        self.clipboard = Some(item);
+        self.compact();
+    }
+
+    // removes the lists nested below an item that is leaving the tree
+    fn free_item_lists(&mut self, item: &Item) {
+        if let Some(list_index) = item.list_index {
+            if let Some(list) = self.lists.remove(list_index) {
+                for nested_item in list.items.iter() {
+                    self.free_item_lists(nested_item);
+                }
+            }
+        }
+    }
+
+    // once most of the arena is free slots, drops any list that can't be
+    // reached from the root or the clipboard and moves the rest together
+    fn compact(&mut self) {
+        if self.lists.free_slots() <= self.lists.live_slots() {
+            return;
+        }
+
+        let mut reachable = HashSet::new();
+        let mut pending = vec![self.root];
+        pending.extend(self.clipboard.iter().filter_map(|item| item.list_index));
+
+        while let Some(list_index) = pending.pop() {
+            if reachable.insert(list_index) {
+                let list = &self.lists[list_index];
+                pending.extend(list.items.iter().filter_map(|item| item.list_index));
+            }
+        }
+
+        let unreachable: Vec<Index> = self.lists.iter()
+            .map(|(list_index, _)| list_index)
+            .filter(|list_index| !reachable.contains(list_index))
+            .collect();
+
+        for list_index in unreachable {
+            self.lists.remove(list_index);
+        }
+
+        let remap = self.lists.compact();
+        self.remap_lists(&remap);
+    }
+
+    fn remap_lists(&mut self, remap: &HashMap<Index, Index>) {
+        let update = |index: &mut Index| {
+            if let Some(new_index) = remap.get(index) {
+                *index = *new_index;
+            }
+        };
+
+        update(&mut self.root);
+        update(&mut self.current);
+
+        for list in self.lists.values_mut() {
+            if let Some(previous) = list.previous.as_mut() {
+                update(previous);
+            }
+
+            for item in list.items.iter_mut() {
+                if let Some(list_index) = item.list_index.as_mut() {
+                    update(list_index);
+                }
+            }
+        }
+
+        if let Some(list_index) = self.clipboard.as_mut().and_then(|item| item.list_index.as_mut()) {
+            update(list_index);
+        }
+    }
+
+    fn duplicate_selected_item(&mut self) {
+        if !self.get_current_page_options().disable_add {
+            if let Some(item) = self.get_selected_item().cloned() {
+                self.checkpoint(format!("duplicate \"{}\"", item.name));
+
+                let copy = self.clone_item(&item, self.current);
+                self.lists[self.current].insert_item(copy.clone(), Position::After);
+
+                if let Some(path) = self.selected_item_path() {
+                    self.emit_added(path, &copy);
+                }
+            }
+        }
+    }
+
+    // copies an item and every list nested below it into new arena slots,
+    // generating a fresh id for each copied item
+    fn clone_item(&mut self, item: &Item, parent_index: Index) -> Item {
+        self.clone_item_reserving(item, parent_index, &mut HashSet::new())
+    }
+
+    // the copy isn't in the tree until every item below it has been copied,
+    // so the ids given out so far are kept in `reserved`
+    fn clone_item_reserving(&mut self, item: &Item, parent_index: Index, reserved: &mut HashSet<String>) -> Item {
+        let mut copy = item.clone();
+        copy.id = self.generate_id_avoiding(&item.name, reserved);
+        reserved.insert(copy.id.clone());
+
+        if let Some(list_index) = item.list_index {
+            let mut list = List::new(self.lists[list_index].name.clone());
+            list.previous = Some(parent_index);
+            list.selected = self.lists[list_index].selected;
+            let copy_index = self.lists.insert(list);
+            for nested_item in self.lists[list_index].items.clone().iter() {
+                let nested_copy = self.clone_item_reserving(nested_item, copy_index, reserved);
+                self.lists[copy_index].items.push(nested_copy);
+            }
+
+            copy.list_index = Some(copy_index);
+        }
+
+        copy
+    }
+
+    // pastes the clipboard into the current list, after the selection
+    fn paste_item(&mut self) {
+        if !self.get_current_page_options().disable_add {
+            if let Some(item) = &self.clipboard {
+                self.checkpoint(format!("paste \"{}\"", item.name));
+            }
+
+            if let Some(item) = self.clipboard.take() {
+                self.link_item_list(&item, self.current);
+                self.lists[self.current].insert_item(item.clone(), Position::After);
+
+                if let Some(path) = self.selected_item_path() {
+                    self.emit_added(path, &item);
+                }
+            }
+        }
+    }
+
+    // pastes the clipboard at the end of the selected item's nested list,
+    // creating the list if the selected item doesn't have one yet
+    fn paste_item_into_selection(&mut self) {
+        if self.get_current_page_options().disable_add || self.get_selected_item().is_none() {
+            return;
+        }
+
+        match &self.clipboard {
+            Some(item) => self.checkpoint(format!("paste \"{}\"", item.name)),
+            None => return,
+        }
+
+        let list_index = match self.get_selected_item() {
+            Some(selected_item) => match selected_item.list_index {
+                Some(list_index) => list_index,
+                None => {
+                    let name = selected_item.name.clone();
+                    let list_index = self.add_list(name);
+                    self.lists[self.current].set_selected_item_list_index(Some(list_index));
+                    list_index
+                }
+            },
+            None => return,
+        };
+
+        if let Some(item) = self.clipboard.take() {
+            self.link_item_list(&item, list_index);
+            self.lists[list_index].items.push(item.clone());
+            self.lists[list_index].expanded = true;
+
+            let path = self.item_path(list_index, self.lists[list_index].items.len() - 1);
+            self.emit_added(path, &item);
+        }
+    }
+
+    // points the back-reference of an item's nested list at the list the
+    // item is being placed in
+    fn link_item_list(&mut self, item: &Item, parent_index: Index) {
+        if let Some(list_index) = item.list_index {
+            self.lists[list_index].previous = Some(parent_index);
+        }
+    }
+
     fn can_go_back(&self) -> bool {
         match self.lists[self.current].previous {
             Some(_) => {
@@ -151,6 +1091,7 @@ impl App{
 
     fn close_current_list(&mut self) {
         self.notification = None;
+        self.filter = None;
 
         match self.lists[self.current].previous {
             Some(previous_index) => {
@@ -165,6 +1106,8 @@ impl App{
                             Some(index) => {
                                 if self.lists[index].items.len() == 0 {
                                     self.lists[self.current].set_selected_item_list_index(None);
+                                    self.lists.remove(index);
+                                    self.compact();
                                 }
                             }
                             None => {}
@@ -179,6 +1122,7 @@ impl App{
 
     fn open_selected_item_list(&mut self) {
         self.notification = None;
+        self.filter = None;
 
         if !self.get_current_page_options().disable_edit {
             match self.lists[self.current].get_selected_item() {
@@ -204,12 +1148,24 @@ impl App{
         if !self.get_current_page_options().disable_delete {
             match self.lists[self.current].get_selected_item() {
                 Some(_) => {
+                    let description = self.describe_selected_item("delete");
+                    self.checkpoint(description);
+
+                    if let Some(item) = self.get_selected_item().cloned() {
+                        if let Some(path) = self.selected_item_path() {
+                            self.emit_removed(path, &item);
+                        }
+                        self.free_item_lists(&item);
+                    }
+
                     self.lists[self.current].set_selected_item_list_index(None);
                     self.lists[self.current].remove_selected_item();
 
                     if self.lists[self.current].items.len() == 0 {
                         self.close_current_list();
                     }
+
+                    self.compact();
                 }
                 None => {}
             }
@@ -217,18 +1173,20 @@ impl App{
     }
 
     pub fn run(&mut self) -> Result<(), failure::Error> {
-        if self.lists.len() == 0 {
+        if !self.lists.contains(self.root) {
             println!("No root list found");
             return Ok(());
         }
 
-        if self.lists[0].items.len() == 0 {
-            println!("No items in root list");
-            return Ok(());
-        }
-
         self.running = true;
 
+        // must outlive the terminal, which restores the screen on drop
+        let _stdout_to_tty = if self.options.use_tty {
+            Some(StdoutToTty::new()?)
+        } else {
+            None
+        };
+
         // Terminal initialization
         let stdout = io::stdout().into_raw_mode()?;
         let stdout = MouseTerminal::from(stdout);
@@ -237,11 +1195,20 @@ impl App{
         let mut terminal = Terminal::new(backend)?;
         terminal.hide_cursor()?;
 
-        let events = Events::new();
+        let events = Events::with_config(Config{
+            tty: self.options.use_tty,
+            ..Config::default()
+        })?;
+        self.events = Some(events.sender());
+        let mut pending_count: Option<usize> = None;
 
         'main: loop {
             let page_options = self.get_current_page_options();
 
+            if self.is_outline() {
+                self.reveal_current_list();
+            }
+
             if !self.running {
                 break 'main;
             }
@@ -260,17 +1227,37 @@ impl App{
                     .style(Style::default().bg(Color::Black));
 
                 let list = self.get_current_list();
-                let mut title = format!("{}: {}", page_options.title, list.name);
+                let mut markers = String::new();
+
+                if self.is_dirty() {
+                    markers.push_str(" [modified]");
+                }
+
+                if self.saving.is_some() {
+                    markers.push_str(" saving…");
+                }
+
+                let prefix = format!("{}: ", page_options.title);
+                let breadcrumb_width = (wrapper_chunks[0].width as usize)
+                    .saturating_sub(2 + prefix.width() + markers.width());
+                let mut title = prefix + &shorten_breadcrumb(&self.get_breadcrumb(), breadcrumb_width);
                 let mut title_color = Color::Gray;
 
                 match self.notification.clone() {
                     Some(notice) => {
-                        title = format!("{}", notice);
-                        title_color = Color::Red;
+                        title = notice.message;
+                        title_color = level_color(notice.level);
                     }
                     None => {}
                 }
 
+                title.push_str(&markers);
+
+                if self.confirming_exit {
+                    title = String::from(EXIT_PROMPT);
+                    title_color = Color::Yellow;
+                }
+
                 Paragraph::new([
                     Text::styled(
                         title,
@@ -291,72 +1278,189 @@ impl App{
                 let info_chunks = Layout::default()
                     .direction(Direction::Vertical)
                     .constraints([
+                        Constraint::Percentage(40),
                         Constraint::Percentage(20),
-                        Constraint::Percentage(20),
-                        Constraint::Percentage(60),
+                        Constraint::Percentage(40),
                     ].as_ref())
                     .split(body_chunks[1]);
 
                 let style = Style::default().fg(Color::Gray);
-                SelectableList::default()
-                    .block(Block::default().borders(Borders::ALL).title(page_options.menu_box_title.as_str()))
-                    .items(&list.items.iter().map(|i| { i.name.clone() }).collect::<Vec<_>>())
-                    .select(list.get_selected_item_index())
-                    .style(style)
-                    .highlight_style(style.fg(Color::LightBlue).modifier(Modifier::BOLD))
-                    .highlight_symbol(">")
-                    .render(&mut f, body_chunks[0]);
-
-                match self.get_selected_item() {
-                    Some(item) => {
-                        let mut usage = vec![
-                            "ctrl-c: exit",
-                        ];
+                let highlight_style = style.fg(Color::LightBlue).modifier(Modifier::BOLD);
+
+                match &self.filter {
+                    Some(filter) => {
+                        let height = body_chunks[0].height.saturating_sub(2) as usize;
+                        let offset = match filter.selected {
+                            Some(selected) if selected >= height => selected - height + 1,
+                            _ => 0,
+                        };
+                        let menu_title = format!("{} /{}", page_options.menu_box_title, filter.query);
+
+                        Paragraph::new(self.get_filter_menu_lines(filter, style, highlight_style).iter())
+                            .block(Block::default().borders(Borders::ALL).title(menu_title.as_str()))
+                            .style(style)
+                            .scroll(offset as u16)
+                            .render(&mut f, body_chunks[0]);
+                    }
+                    None if self.is_outline() => {
+                        self.menu_height.set(body_chunks[0].height.saturating_sub(2) as usize);
+
+                        let rows = self.get_outline_rows();
+                        let labels: Vec<String> = rows.iter().map(|(list_index, position, guides)| {
+                            format!("{}{}", guides, self.get_menu_label(&self.lists[*list_index].items[*position]))
+                        }).collect();
+
+                        SelectableList::default()
+                            .block(Block::default().borders(Borders::ALL).title(page_options.menu_box_title.as_str()))
+                            .items(&labels)
+                            .select(self.get_outline_cursor_row(&rows))
+                            .style(style)
+                            .highlight_style(highlight_style)
+                            .highlight_symbol(">")
+                            .render(&mut f, body_chunks[0]);
+                    }
+                    None => {
+                        self.menu_height.set(body_chunks[0].height.saturating_sub(2) as usize);
+
+                        SelectableList::default()
+                            .block(Block::default().borders(Borders::ALL).title(page_options.menu_box_title.as_str()))
+                            .items(&list.items.iter().map(|i| { self.get_menu_label(i) }).collect::<Vec<_>>())
+                            .select(list.get_selected_item_index())
+                            .style(style)
+                            .highlight_style(highlight_style)
+                            .highlight_symbol(">")
+                            .render(&mut f, body_chunks[0]);
+                    }
+                }
 
-                        let save_description = format!(
-                            "W: {}",
-                            page_options.save_command_description.clone()
-                        );
+                let mut usage = vec![
+                    "ctrl-c: exit",
+                ];
 
-                        if !page_options.disable_save {
-                            usage.push(save_description.as_str());
-                        }
+                let save_description = format!(
+                    "W: {}",
+                    page_options.save_command_description.clone()
+                );
 
-                        if self.can_go_back() {
-                            usage.push("b: back to previous page");
-                        }
+                if !page_options.disable_save {
+                    usage.push(save_description.as_str());
+                }
 
-                        if !page_options.disable_add {
-                            usage.push("a: add items to selection");
-                        }
+                if self.is_outline() {
+                    usage.push("left/right: collapse/expand");
+                    usage.push("v: drill-down view");
+                } else {
+                    usage.push("v: outline view");
+                }
 
-                        if self.can_go_forward() {
-                            if !page_options.disable_edit {
-                                usage.push("e: enter selection");
-                            }
-                        }
+                if self.can_go_back() && !self.is_outline() {
+                    usage.push("b: back to previous page");
+                }
 
-                        if !page_options.disable_delete {
-                            usage.push("d: delete selection");
-                        }
+                if !list.items.is_empty() || self.is_outline() {
+                    usage.push("h/j/k/l: same as the arrow keys");
+                    usage.push("g/G, PgUp/PgDn: first/last, page up/down");
+                }
 
-                        let usage_info = usage.iter().map(|i| {
-                            Text::styled(
-                                format!("{}", i),
-                                Style::default().fg(Color::Gray),
-                            )
-                        });
+                let selected_item = self.get_selected_item();
 
-                        TuiList::new(usage_info)
-                            .block(Block::default().borders(Borders::ALL).title("Navigation"))
-                            .start_corner(Corner::TopLeft)
-                            .render(&mut f, info_chunks[0]);
+                if !page_options.disable_add {
+                    if selected_item.is_some() {
+                        usage.push("a: add items to selection");
+                        usage.push("o: add items after selection");
+                        usage.push("O: add items before selection");
+                    }
+                    usage.push("A: add items to end of list");
+                }
+
+                if self.can_go_forward() && !self.is_outline() {
+                    if !page_options.disable_edit {
+                        usage.push("e: enter selection");
+                    }
+                }
+
+                if selected_item.is_some() && !page_options.disable_edit {
+                    usage.push("space: check/uncheck selection");
+                    usage.push("r: rename selection");
+                    usage.push("m: set metadata on selection");
+                }
+
+                if selected_item.is_some() && !page_options.disable_edit {
+                    usage.push("K/J: move selection up/down");
+                    usage.push("T/B: move selection to top/bottom");
+                }
 
-                        let fields = vec![
+                if selected_item.is_some() && !page_options.disable_delete {
+                    usage.push("d: delete selection");
+                    usage.push("x: cut selection");
+                }
+
+                if selected_item.is_some() {
+                    usage.push("y: copy selection");
+                }
+
+                if !list.items.is_empty() {
+                    usage.push("/: filter list");
+                    usage.push("f: find in whole tree");
+                }
+
+                if self.history.can_undo() {
+                    usage.push("u: undo");
+                }
+
+                if self.history.can_redo() {
+                    usage.push("ctrl-r: redo");
+                }
+
+                if selected_item.is_some() && !page_options.disable_add {
+                    usage.push("D: duplicate selection");
+                }
+
+                let paste_descriptions = match &self.clipboard {
+                    Some(item) if !page_options.disable_add => vec![
+                        format!("p: paste \"{}\" after selection", item.name),
+                        format!("P: paste \"{}\" into selection", item.name),
+                    ],
+                    _ => Vec::new(),
+                };
+
+                if let Some(description) = paste_descriptions.first() {
+                    usage.push(description.as_str());
+                }
+
+                if selected_item.is_some() {
+                    if let Some(description) = paste_descriptions.get(1) {
+                        usage.push(description.as_str());
+                    }
+                }
+
+                if self.filter.is_some() {
+                    usage = FILTER_USAGE.to_vec();
+                }
+
+                let usage_info = usage.iter().map(|i| {
+                    Text::styled(
+                        format!("{}", i),
+                        Style::default().fg(Color::Gray),
+                    )
+                });
+
+                TuiList::new(usage_info)
+                    .block(Block::default().borders(Borders::ALL).title("Navigation"))
+                    .start_corner(Corner::TopLeft)
+                    .render(&mut f, info_chunks[0]);
+
+                match selected_item {
+                    Some(item) => {
+                        let mut fields = vec![
                             format!("ID: {}", item.id),
                             format!("Name: {}", item.name),
                         ];
 
+                        for (key, value) in item.metadata.iter() {
+                            fields.push(format!("{}: {}", key, value));
+                        }
+
                         let item_info = fields.iter().map(|i| {
                             Text::styled(
                                 format!("{}", i),
@@ -390,26 +1494,163 @@ impl App{
                 }
             })?;
 
-            match events.next()? {
+            let event = events.next()?;
+
+            // a number typed before a movement key repeats it
+            let count = match event {
+                Event::Input(_) => pending_count.take(),
+                _ => None,
+            };
+            let repeat = count.unwrap_or(1);
+
+            match event {
+                Event::Tick => {
+                    self.tick();
+                }
+                Event::Saved(notification) => {
+                    self.finish_save(notification);
+                }
+                Event::Input(input) if self.filter.is_some() => {
+                    self.handle_filter_input(input);
+                }
+                Event::Input(input) if self.confirming_exit => {
+                    match input {
+                        Key::Char('s') => {
+                            self.save();
+                            self.exit_after_save = true;
+                        }
+                        Key::Char('d') => {
+                            self.stop();
+                        }
+                        Key::Char('c') | Key::Esc => {}
+                        _ => continue,
+                    }
+                    self.confirming_exit = false;
+                }
+                Event::Input(Key::Char(c)) if c.is_ascii_digit() && (c != '0' || count.is_some()) => {
+                    let digit = c.to_digit(10).unwrap_or(0) as usize;
+                    pending_count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                }
                 Event::Input(input) => match input {
                     Key::Ctrl('c') => {
-                        break;
+                        self.quit();
                     }
-                    Key::Left => {
-                        self.close_current_list();
+                    Key::Char('v') => {
+                        self.toggle_view_mode();
                     }
-                    Key::Right => {
-                        self.open_selected_item_list();
+                    Key::Left | Key::Char('b') | Key::Char('h') if self.is_outline() => {
+                        for _ in 0..repeat {
+                            self.collapse_selected_item();
+                        }
+                    }
+                    Key::Right | Key::Char('e') | Key::Char('l') if self.is_outline() => {
+                        for _ in 0..repeat {
+                            self.expand_selected_item();
+                        }
+                    }
+                    Key::Down | Key::Char('j') if count.is_some() => {
+                        self.select_menu_row(|row, _| row.saturating_add(repeat));
                     }
-                    Key::Down => {
+                    Key::Up | Key::Char('k') if count.is_some() => {
+                        self.select_menu_row(|row, _| row.saturating_sub(repeat));
+                    }
+                    Key::Down | Key::Char('j') if self.is_outline() => {
+                        self.move_outline_cursor(true);
+                    }
+                    Key::Up | Key::Char('k') if self.is_outline() => {
+                        self.move_outline_cursor(false);
+                    }
+                    Key::PageDown => {
+                        let page = self.menu_height.get().max(1).saturating_mul(repeat);
+                        self.select_menu_row(|row, _| row.saturating_add(page));
+                    }
+                    Key::PageUp => {
+                        let page = self.menu_height.get().max(1).saturating_mul(repeat);
+                        self.select_menu_row(|row, _| row.saturating_sub(page));
+                    }
+                    Key::Home | Key::Char('g') => {
+                        self.select_menu_row(|_, _| 0);
+                    }
+                    // with a count, goes to that row like vim's `5G`
+                    Key::End | Key::Char('G') => {
+                        self.select_menu_row(|_, rows| count.map_or(rows, |count| count.max(1)) - 1);
+                    }
+                    Key::Left | Key::Char('h') => {
+                        for _ in 0..repeat {
+                            self.close_current_list();
+                        }
+                    }
+                    Key::Right | Key::Char('l') => {
+                        for _ in 0..repeat {
+                            self.open_selected_item_list();
+                        }
+                    }
+                    Key::Down | Key::Char('j') => {
                         (&mut self.lists[self.current]).decrement_selected();
                     }
-                    Key::Up => {
+                    Key::Up | Key::Char('k') => {
                         (&mut self.lists[self.current]).increment_selected();
                     }
                     Key::Char('W') => {
                         self.save();
                     }
+                    Key::Char('/') => {
+                        self.start_filter();
+                    }
+                    Key::Char('f') => {
+                        let mut query = String::new();
+                        let mut results = search::search(&self.lists, self.root, &query);
+                        let mut selected = 0;
+
+                        loop {
+                            if !self.running {
+                                break 'main;
+                            }
+
+                            draw_search_menu(&mut terminal, self, &query, &results, selected)?;
+
+                            match events.next()? {
+                                Event::Tick => {
+                                    self.tick();
+                                }
+                                Event::Saved(notification) => {
+                                    self.finish_save(notification);
+                                }
+                                Event::Input(input) => match input {
+                                    Key::Ctrl('c') => {
+                                        self.quit();
+                                        break;
+                                    }
+                                    Key::Esc => {
+                                        break;
+                                    }
+                                    Key::Char('\n') => {
+                                        if let Some(result) = results.get(selected) {
+                                            self.jump_to(&result.trail);
+                                        }
+                                        break;
+                                    }
+                                    Key::Up => {
+                                        selected = selected.saturating_sub(1);
+                                    }
+                                    Key::Down if selected + 1 < results.len() => {
+                                        selected += 1;
+                                    }
+                                    Key::Char(c) => {
+                                        query.push(c);
+                                        results = search::search(&self.lists, self.root, &query);
+                                        selected = 0;
+                                    }
+                                    Key::Backspace => {
+                                        query.pop();
+                                        results = search::search(&self.lists, self.root, &query);
+                                        selected = 0;
+                                    }
+                                    _ => {}
+                                },
+                            }
+                        }
+                    }
                     Key::Char('b') => {
                         self.close_current_list();
                     }
@@ -422,24 +1663,30 @@ impl App{
                                     break 'main;
                                 }
 
-                                draw_add_menu(&mut terminal, &self, user_input.clone())?;
+                                draw_input_menu(&mut terminal, &self, InputTarget::SelectedItem, &ADD_USAGE, user_input.clone())?;
 
                                 // Handle input
                                 match events.next()? {
+                                    Event::Tick => {
+                                        self.tick();
+                                    }
+                                    Event::Saved(notification) => {
+                                        self.finish_save(notification);
+                                    }
                                     Event::Input(input) => match input {
                                         Key::Ctrl('c') => {
-                                            break 'main;
+                                            self.quit();
+                                            break;
                                         }
                                         Key::Ctrl('s') => {
                                             break;
                                         }
                                         Key::Char('\n') => {
                                             if user_input != "" {
-                                                let input: String = user_input.drain(..).collect();
-                                                let id = input.clone();
-                                                let name = input.clone();
+                                                let name: String = user_input.drain(..).collect();
+                                                let id = self.generate_id(&name);
 
-                                                self.add_list_item(id, name);
+                                                self.add_list_item(name, id);
                                             }
                                         }
                                         Key::Char(c) => {
@@ -454,22 +1701,359 @@ impl App{
                             };
                         }
                     }
+                    Key::Char(' ') => {
+                        self.toggle_selected_item_done();
+                    }
+                    Key::Char('m') if !page_options.disable_edit => {
+                        if self.get_selected_item().is_none() {
+                            continue;
+                        }
+
+                        let mut user_input = String::new();
+
+                        loop {
+                            if !self.running {
+                                break 'main;
+                            }
+
+                            draw_input_menu(&mut terminal, self, InputTarget::SelectedItem, &METADATA_USAGE, user_input.clone())?;
+
+                            match events.next()? {
+                                Event::Tick => {
+                                    self.tick();
+                                }
+                                Event::Saved(notification) => {
+                                    self.finish_save(notification);
+                                }
+                                Event::Input(input) => match input {
+                                    Key::Ctrl('c') => {
+                                        self.quit();
+                                        break;
+                                    }
+                                    Key::Esc => {
+                                        break;
+                                    }
+                                    Key::Char('\n') => {
+                                        if let Some((key, value)) = user_input.split_once('=') {
+                                            let key = key.trim();
+
+                                            if !key.is_empty() {
+                                                self.set_selected_item_metadata(key.to_string(), value.trim().to_string());
+                                            }
+                                        }
+                                        break;
+                                    }
+                                    Key::Char(c) => {
+                                        user_input.push(c);
+                                    }
+                                    Key::Backspace => {
+                                        user_input.pop();
+                                    }
+                                    _ => {}
+                                },
+                            }
+                        }
+                    }
+                    Key::Char(c @ 'o') | Key::Char(c @ 'O') | Key::Char(c @ 'A') if !page_options.disable_add => {
+                        let mut position = match c {
+                            'o' => Position::After,
+                            'O' => Position::Before,
+                            _ => Position::End,
+                        };
+                        let mut user_input = String::new();
+
+                        loop {
+                            if !self.running {
+                                break 'main;
+                            }
+
+                            draw_input_menu(&mut terminal, self, InputTarget::CurrentList, &ADD_USAGE, user_input.clone())?;
+
+                            match events.next()? {
+                                Event::Tick => {
+                                    self.tick();
+                                }
+                                Event::Saved(notification) => {
+                                    self.finish_save(notification);
+                                }
+                                Event::Input(input) => match input {
+                                    Key::Ctrl('c') => {
+                                        self.quit();
+                                        break;
+                                    }
+                                    Key::Ctrl('s') => {
+                                        break;
+                                    }
+                                    Key::Char('\n') => {
+                                        if !user_input.is_empty() {
+                                            let name = std::mem::take(&mut user_input);
+                                            let id = self.generate_id(&name);
+
+                                            self.add_sibling_item(name, id, position);
+
+                                            // the new item is now selected, so keep adding after it
+                                            // to preserve the order items were typed in
+                                            if position == Position::Before {
+                                                position = Position::After;
+                                            }
+                                        }
+                                    }
+                                    Key::Char(c) => {
+                                        user_input.push(c);
+                                    }
+                                    Key::Backspace => {
+                                        user_input.pop();
+                                    }
+                                    _ => {}
+                                },
+                            }
+                        }
+                    }
                     Key::Char('e') => {
                         self.open_selected_item_list();
                     }
+                    Key::Char('r') if !page_options.disable_edit => {
+                        let mut user_input = match self.get_selected_item() {
+                            Some(item) => item.name.clone(),
+                            None => continue,
+                        };
+
+                        loop {
+                            if !self.running {
+                                break 'main;
+                            }
+
+                            draw_input_menu(&mut terminal, self, InputTarget::SelectedItem, &RENAME_USAGE, user_input.clone())?;
+
+                            match events.next()? {
+                                Event::Tick => {
+                                    self.tick();
+                                }
+                                Event::Saved(notification) => {
+                                    self.finish_save(notification);
+                                }
+                                Event::Input(input) => match input {
+                                    Key::Ctrl('c') => {
+                                        self.quit();
+                                        break;
+                                    }
+                                    Key::Esc => {
+                                        break;
+                                    }
+                                    Key::Char('\n') => {
+                                        if !user_input.is_empty() {
+                                            self.rename_selected_item(user_input);
+                                        }
+                                        break;
+                                    }
+                                    Key::Char(c) => {
+                                        user_input.push(c);
+                                    }
+                                    Key::Backspace => {
+                                        user_input.pop();
+                                    }
+                                    _ => {}
+                                },
+                            }
+                        }
+                    }
                     Key::Char('d') => {
                         self.delete_selected_item();
                     }
+                    Key::Char('x') => {
+                        self.cut_selected_item();
+                    }
+                    Key::Char('u') => {
+                        self.undo();
+                    }
+                    Key::Ctrl('r') => {
+                        self.redo();
+                    }
+                    Key::Char('y') => {
+                        self.copy_selected_item();
+                    }
+                    Key::Char('D') => {
+                        self.duplicate_selected_item();
+                    }
+                    Key::Char('p') => {
+                        self.paste_item();
+                    }
+                    Key::Char('P') => {
+                        self.paste_item_into_selection();
+                    }
+                    Key::Char('K') => {
+                        self.move_selected_item(|selected, _| selected.saturating_sub(1));
+                    }
+                    Key::Char('J') => {
+                        self.move_selected_item(|selected, _| selected + 1);
+                    }
+                    Key::Char('T') => {
+                        self.move_selected_item(|_, _| 0);
+                    }
+                    Key::Char('B') => {
+                        self.move_selected_item(|_, len| len - 1);
+                    }
                     _ => {}
                 },
             }
         }
+
+        // a save still running gets to finish, along with one waiting on it
+        while self.saving.is_some() {
+            if let Event::Saved(notification) = events.next()? {
+                self.finish_save(notification);
+            }
+        }
+        self.events = None;
+
         Ok(())
     }
 
 }
 
-fn draw_add_menu(terminal: &mut Term, app: &App, user_input: String) -> Result<(), failure::Error> {
+enum InputTarget {
+    SelectedItem,
+    CurrentList,
+}
+
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(4);
+
+fn level_color(level: Level) -> Color {
+    match level {
+        Level::Info => Color::LightBlue,
+        Level::Success => Color::Green,
+        Level::Warning => Color::Yellow,
+        Level::Error => Color::Red,
+    }
+}
+
+const EXIT_PROMPT: &str = "Unsaved changes. s: save and exit, d: discard and exit, c: cancel";
+
+// splits a name into segments so the matched characters can be styled
+// differently from the rest
+fn highlight_matches(name: &str, matched: &[usize], style: Style, match_style: Style) -> Vec<Text<'static>> {
+    let mut segments = Vec::new();
+    let mut segment = String::new();
+    let mut segment_matched = false;
+
+    for (i, c) in name.chars().enumerate() {
+        let is_matched = matched.contains(&i);
+        if is_matched != segment_matched && !segment.is_empty() {
+            let segment_style = if segment_matched { match_style } else { style };
+            segments.push(Text::styled(std::mem::take(&mut segment), segment_style));
+        }
+        segment_matched = is_matched;
+        segment.push(c);
+    }
+
+    let segment_style = if segment_matched { match_style } else { style };
+    segments.push(Text::styled(segment, segment_style));
+    segments
+}
+
+const BREADCRUMB_SEPARATOR: &str = " / ";
+
+// joins the names into a path that fits in `width`, leaving out names from
+// the middle first and then characters from the middle of what is left,
+// keeping the root and the current list for as long as possible
+fn shorten_breadcrumb(names: &[String], width: usize) -> String {
+    let path = names.join(BREADCRUMB_SEPARATOR);
+    if path.width() <= width || names.len() <= 2 {
+        return shorten_middle(&path, width);
+    }
+
+    let (root, rest) = names.split_first().unwrap();
+    for kept in (1..rest.len()).rev() {
+        let mut shortened = vec![root.as_str(), "…"];
+        shortened.extend(rest[rest.len() - kept..].iter().map(String::as_str));
+
+        let path = shortened.join(BREADCRUMB_SEPARATOR);
+        if path.width() <= width {
+            return path;
+        }
+    }
+
+    let path = [root.as_str(), "…", rest[rest.len() - 1].as_str()].join(BREADCRUMB_SEPARATOR);
+    shorten_middle(&path, width)
+}
+
+// cuts characters out of the middle of text that is wider than `width`,
+// marking where with "…"
+fn shorten_middle(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let available = width - 1;
+    let mut head = String::new();
+    let mut tail = Vec::new();
+    let (mut head_width, mut tail_width) = (0, 0);
+    let (mut front, mut back) = (0, chars.len());
+
+    // take characters from each end in turn until the space is used up
+    while front < back {
+        let take_front = head_width <= tail_width;
+        let c = if take_front { chars[front] } else { chars[back - 1] };
+        let c_width = UnicodeWidthChar::width(c).unwrap_or(0);
+
+        if head_width + tail_width + c_width > available {
+            break;
+        }
+
+        if take_front {
+            head.push(c);
+            head_width += c_width;
+            front += 1;
+        } else {
+            tail.push(c);
+            tail_width += c_width;
+            back -= 1;
+        }
+    }
+
+    head.push('…');
+    head.extend(tail.iter().rev());
+    head
+}
+
+fn get_menu_checkbox(item: &Item) -> &'static str {
+    if item.done { "[x]" } else { "[ ]" }
+}
+
+const FILTER_USAGE: [&str; 5] = [
+    "type to filter by name",
+    "up/down: move between matches",
+    "enter: go to selection",
+    "left/right: leave list",
+    "esc: cancel",
+];
+
+const SEARCH_USAGE: [&str; 3] = [
+    "up/down: move between results",
+    "enter: go to result",
+    "esc: cancel",
+];
+
+const ADD_USAGE: [&str; 1] = [
+    "ctrl-s: save and return to previous",
+];
+
+const METADATA_USAGE: [&str; 3] = [
+    "enter: save field, typed as key=value",
+    "key= with no value removes the field",
+    "esc: cancel",
+];
+
+const RENAME_USAGE: [&str; 2] = [
+    "enter: save new name",
+    "esc: cancel",
+];
+
+fn draw_input_menu(terminal: &mut Term, app: &App, target: InputTarget, usage: &[&str], user_input: String) -> Result<(), failure::Error> {
     let page_options = app.get_current_page_options();
 
     terminal.draw(|mut f| {
@@ -487,16 +2071,10 @@ fn draw_add_menu(terminal: &mut Term, app: &App, user_input: String) -> Result<(
             .borders(Borders::ALL)
             .style(Style::default().bg(Color::Black));
 
-        let title: String;
-        match app.get_selected_item() {
-            Some(item) => {
-                title = format!("{}", item.name);
-            }
-            None => {
-                let list = app.get_current_list();
-                title = format!("{}", list.name);
-            }
-        }
+        let title = match (&target, app.get_selected_item()) {
+            (InputTarget::SelectedItem, Some(item)) => item.name.clone(),
+            _ => app.get_current_list().name.clone(),
+        };
 
         Paragraph::new([
             Text::styled(
@@ -507,10 +2085,6 @@ fn draw_add_menu(terminal: &mut Term, app: &App, user_input: String) -> Result<(
             .alignment(Alignment::Center)
             .render(&mut f, wrapper_chunks[0]);
 
-        let usage = vec![
-            "ctrl-s: save and return to previous",
-        ];
-
         let usage_info = usage.iter().map(|i| {
             Text::styled(
                 format!("{}", i),
@@ -533,7 +2107,12 @@ fn draw_add_menu(terminal: &mut Term, app: &App, user_input: String) -> Result<(
             .block(Block::default().borders(Borders::ALL).title("Input"))
             .render(&mut f, chunks[0]);
 
-        match app.get_list_for_selected_item() {
+        let preview = match target {
+            InputTarget::SelectedItem => app.get_list_for_selected_item(),
+            InputTarget::CurrentList => Some(app.get_current_list()),
+        };
+
+        match preview {
             Some(list) => {
                 let text_list = list
                     .items
@@ -557,3 +2136,432 @@ fn draw_add_menu(terminal: &mut Term, app: &App, user_input: String) -> Result<(
     io::stdout().flush().ok();
     Ok(())
 }
+
+
+fn draw_search_menu(terminal: &mut Term, app: &App, query: &str, results: &[SearchResult], selected: usize) -> Result<(), failure::Error> {
+    terminal.draw(|mut f| {
+        let wrapper_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(2)
+            .constraints([
+                Constraint::Percentage(10),
+                Constraint::Percentage(20),
+                Constraint::Percentage(70),
+            ].as_ref())
+            .split(f.size());
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black));
+
+        Paragraph::new([
+            Text::styled(
+                format!("Find in {}", app.lists[app.root].name),
+                Style::default().fg(Color::Gray).modifier(Modifier::BOLD),
+            )].iter())
+            .block(block)
+            .alignment(Alignment::Center)
+            .render(&mut f, wrapper_chunks[0]);
+
+        let usage_info = SEARCH_USAGE.iter().map(|i| {
+            Text::styled(
+                i.to_string(),
+                Style::default().fg(Color::Gray),
+            )
+        });
+
+        TuiList::new(usage_info)
+            .block(Block::default().borders(Borders::ALL).title("Navigation"))
+            .start_corner(Corner::TopLeft)
+            .render(&mut f, wrapper_chunks[1]);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+            .split(wrapper_chunks[2]);
+
+        Paragraph::new([Text::raw(query)].iter())
+            .style(Style::default().fg(Color::Yellow))
+            .block(Block::default().borders(Borders::ALL).title("Find"))
+            .render(&mut f, chunks[0]);
+
+        let style = Style::default().fg(Color::Gray);
+        let highlight_style = style.fg(Color::LightBlue).modifier(Modifier::BOLD);
+        let mut lines = Vec::new();
+
+        for (position, result) in results.iter().enumerate() {
+            let line_style = if position == selected { highlight_style } else { style };
+            let symbol = if position == selected { ">" } else { " " };
+
+            lines.push(Text::styled(format!("{} ", symbol), line_style));
+            lines.extend(highlight_matches(&result.name, &result.positions, line_style, line_style.fg(Color::Yellow)));
+            lines.push(Text::styled(
+                format!("  {}", result.breadcrumb.join(BREADCRUMB_SEPARATOR)),
+                Style::default().fg(Color::DarkGray),
+            ));
+            lines.push(Text::raw("\n"));
+        }
+
+        let height = chunks[1].height.saturating_sub(2) as usize;
+        let offset = if selected >= height { selected - height + 1 } else { 0 };
+        let results_title = format!("Results: {}", results.len());
+
+        Paragraph::new(lines.iter())
+            .block(Block::default().borders(Borders::ALL).title(results_title.as_str()))
+            .scroll(offset as u16)
+            .render(&mut f, chunks[1]);
+    })?;
+
+    write!(
+        terminal.backend_mut(),
+        "{}",
+        Goto(4 + query.width() as u16, 4)
+    )?;
+    io::stdout().flush().ok();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn app(outline: &str) -> App {
+        crate::init_app(crate::format::text::from_str(outline))
+    }
+
+    fn outline(app: &App) -> String {
+        crate::format::text::to_string(&app.user_list())
+    }
+
+    fn select(app: &mut App, position: usize) {
+        app.lists[app.current].set_selected_item_index(Some(position));
+    }
+
+    // logs each change the app reports as a short description
+    fn record_changes(app: &mut App) -> Rc<RefCell<Vec<String>>> {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let handler_log = Rc::clone(&log);
+
+        app.register_change_handler(Box::new(move |change| {
+            let description = match change {
+                Change::ItemAdded{path, item} => format!("add {} at {:?}", item.name, path),
+                Change::ItemRemoved{path, item} => format!("remove {} at {:?}", item.name, path),
+                Change::ItemRenamed{path, name} => format!("rename {:?} to {}", path, name),
+                Change::ItemMoved{from, to} => format!("move {:?} to {:?}", from, to),
+                Change::ItemUpdated{path, done, ..} => format!("update {:?} done {}", path, done),
+            };
+            handler_log.borrow_mut().push(description);
+        }));
+
+        log
+    }
+
+    fn collect_ids(items: &[crate::Item], ids: &mut Vec<String>) {
+        for item in items {
+            ids.push(item.id.clone());
+            if let Some(list) = &item.list {
+                collect_ids(&list.items, ids);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_a_breadcrumb_that_fits() {
+        assert_eq!(shorten_breadcrumb(&names(&["root", "a", "b"]), 12), "root / a / b");
+        assert_eq!(shorten_breadcrumb(&names(&["root"]), 4), "root");
+    }
+
+    #[test]
+    fn leaves_out_names_from_the_middle_first() {
+        let path = names(&["root", "first", "second", "third"]);
+
+        assert_eq!(shorten_breadcrumb(&path, 26), "root / … / second / third");
+        assert_eq!(shorten_breadcrumb(&path, 17), "root / … / third");
+    }
+
+    #[test]
+    fn cuts_into_the_names_once_only_the_ends_are_left() {
+        let path = names(&["root", "first", "a very long list name"]);
+        let shortened = shorten_breadcrumb(&path, 20);
+
+        assert_eq!(shortened.width(), 20);
+        assert!(shortened.starts_with("root / …"));
+        assert!(shortened.ends_with("list name"));
+    }
+
+    #[test]
+    fn cuts_a_breadcrumb_of_two_names_from_the_middle() {
+        assert_eq!(shorten_breadcrumb(&names(&["root", "current"]), 12), "root /…rrent");
+        assert_eq!(shorten_breadcrumb(&names(&["root", "current"]), 13), "root /…urrent");
+    }
+
+    #[test]
+    fn cuts_characters_from_the_middle() {
+        assert_eq!(shorten_middle("abcdefgh", 8), "abcdefgh");
+        assert_eq!(shorten_middle("abcdefgh", 5), "ab…gh");
+        assert_eq!(shorten_middle("abcdefgh", 4), "ab…h");
+        assert_eq!(shorten_middle("abcdefgh", 1), "…");
+        assert_eq!(shorten_middle("abcdefgh", 0), "");
+    }
+
+    #[test]
+    fn measures_wide_characters_by_their_width() {
+        let shortened = shorten_middle("日本語のリスト", 7);
+
+        assert_eq!(shortened, "日本…ト");
+        assert!(shortened.width() <= 7);
+    }
+
+    #[test]
+    fn pastes_a_cut_item_with_its_lists_linked_to_their_new_place() {
+        let mut app = app("a\n\tx\n\t\tx1\nb\n");
+
+        app.open_selected_item_list();
+        app.cut_selected_item();
+
+        assert_eq!(app.current, app.root);
+        assert_eq!(app.depth, 0);
+        assert_eq!(outline(&app), "a\nb\n");
+
+        select(&mut app, 1);
+        app.paste_item_into_selection();
+
+        let b_list = app.lists[app.root].items[1].list_index.unwrap();
+        let x_list = app.lists[b_list].items[0].list_index.unwrap();
+        assert_eq!(app.lists[x_list].previous, Some(b_list));
+        assert_eq!(app.list_path(x_list), [1, 0]);
+        assert_eq!(outline(&app), "a\nb\n\tx\n\t\tx1\n");
+
+        app.open_selected_item_list();
+        app.open_selected_item_list();
+        assert_eq!(app.current, x_list);
+        assert_eq!(app.depth, 2);
+        assert_eq!(app.get_breadcrumb(), ["", "b", "x"]);
+
+        app.close_current_list();
+        app.close_current_list();
+        assert_eq!(app.current, app.root);
+        assert_eq!(app.depth, 0);
+    }
+
+    #[test]
+    fn gives_every_item_in_a_duplicated_subtree_a_new_id() {
+        let item = |id: &str, name: &str, items: Vec<crate::Item>| {
+            let list = if items.is_empty() { None } else { Some(crate::List::new(name.to_string(), items)) };
+            crate::Item::new(id.to_string(), name.to_string(), list)
+        };
+        let mut app = crate::init_app(crate::List::new(String::new(), vec![
+            item("a", "a", vec![
+                item("x-1", "x", vec![item("x-2", "x", vec![])]),
+                item("y", "y", vec![]),
+            ]),
+            item("b", "b", vec![]),
+        ]));
+
+        app.duplicate_selected_item();
+        app.duplicate_selected_item();
+
+        let mut all_ids = Vec::new();
+        collect_ids(&app.user_list().items, &mut all_ids);
+        let unique: HashSet<&String> = all_ids.iter().collect();
+
+        assert_eq!(outline(&app), "a\n\tx\n\t\tx\n\ty\na\n\tx\n\t\tx\n\ty\na\n\tx\n\t\tx\n\ty\nb\n");
+        assert_eq!(unique.len(), all_ids.len());
+    }
+
+    #[test]
+    fn keeps_only_as_many_undo_steps_as_the_history_depth() {
+        let mut app = app("a\n");
+        for name in ["b", "c", "d"].iter() {
+            app.rename_selected_item(name.to_string());
+        }
+
+        app.set_history_depth(2);
+        app.undo();
+        app.undo();
+
+        assert_eq!(outline(&app), "b\n");
+        assert!(!app.history.can_undo());
+
+        app.undo();
+        assert_eq!(outline(&app), "b\n");
+    }
+
+    #[test]
+    fn forgets_what_could_be_redone_after_a_new_edit() {
+        let mut app = app("a\n");
+        app.rename_selected_item(String::from("b"));
+        app.undo();
+        assert!(app.history.can_redo());
+
+        app.rename_selected_item(String::from("c"));
+        assert!(!app.history.can_redo());
+
+        app.redo();
+        assert_eq!(outline(&app), "c\n");
+    }
+
+    #[test]
+    fn is_clean_again_after_undoing_back_to_the_saved_state() {
+        let mut app = app("a\n");
+        app.rename_selected_item(String::from("b"));
+        app.saving = Some(app.revision);
+        app.finish_save(None);
+        assert!(!app.is_dirty());
+
+        app.rename_selected_item(String::from("c"));
+        assert!(app.is_dirty());
+
+        app.undo();
+        assert!(!app.is_dirty());
+
+        app.redo();
+        assert!(app.is_dirty());
+
+        app.undo();
+        app.undo();
+        assert_eq!(outline(&app), "a\n");
+        assert!(app.is_dirty());
+    }
+
+    #[test]
+    fn remaps_the_lists_when_a_delete_compacts_the_arena() {
+        let mut app = app("a\n\tb\n\t\tc\n\t\t\td\nh\n\ti\n");
+        let root = app.root;
+
+        app.delete_selected_item();
+
+        assert_ne!(app.root, root);
+        assert_eq!(app.current, app.root);
+        assert_eq!(app.lists.free_slots(), 0);
+        let h_list = app.lists[app.root].items[0].list_index.unwrap();
+        assert_eq!(app.lists[h_list].previous, Some(app.root));
+        assert_eq!(outline(&app), "h\n\ti\n");
+
+        app.undo();
+        assert_eq!(app.root, root);
+        assert_eq!(outline(&app), "a\n\tb\n\t\tc\n\t\t\td\nh\n\ti\n");
+
+        app.redo();
+        assert_eq!(outline(&app), "h\n\ti\n");
+        app.open_selected_item_list();
+        assert_eq!(app.get_breadcrumb(), ["", "h"]);
+    }
+
+    #[test]
+    fn remaps_the_lists_when_a_cut_compacts_the_arena() {
+        let original = "a\n\tb\n\t\tc\n\t\t\td\n\t\t\t\te\n\t\t\t\t\tx\nh\n\ti\n\t\tf\n\t\t\tg\n\t\tj\n";
+        let mut app = app(original);
+        let root = app.root;
+
+        app.cut_selected_item();
+        app.open_selected_item_list();
+        app.open_selected_item_list();
+        // replacing the clipboard frees the lists below "a"
+        app.cut_selected_item();
+
+        assert_ne!(app.root, root);
+        assert_eq!(app.lists.free_slots(), 0);
+        assert_eq!(app.depth, 2);
+        let h_list = app.lists[app.root].items[0].list_index.unwrap();
+        assert_eq!(app.lists[h_list].items[0].list_index, Some(app.current));
+        assert_eq!(app.lists[app.current].previous, Some(h_list));
+        assert_eq!(app.lists[h_list].previous, Some(app.root));
+        assert_eq!(app.list_path(app.current), [0, 0]);
+
+        let f_list = app.clipboard.as_ref().and_then(|item| item.list_index).unwrap();
+        assert_eq!(app.lists[f_list].items[0].name, "g");
+
+        app.paste_item();
+        assert_eq!(app.lists[f_list].previous, Some(app.current));
+        let pasted = outline(&app);
+        assert_eq!(pasted, "h\n\ti\n\t\tj\n\t\tf\n\t\t\tg\n");
+
+        for _ in 0..3 {
+            app.undo();
+        }
+        assert_eq!(app.root, root);
+        assert_eq!(outline(&app), original);
+
+        for _ in 0..3 {
+            app.redo();
+        }
+        assert_eq!(outline(&app), pasted);
+        app.close_current_list();
+        app.close_current_list();
+        assert_eq!(app.current, app.root);
+        assert_eq!(app.depth, 0);
+    }
+
+    #[test]
+    fn reports_siblings_at_the_path_they_were_inserted_at() {
+        let mut app = app("a\n\tb\n\tc\n");
+        let log = record_changes(&mut app);
+
+        app.open_selected_item_list();
+        select(&mut app, 1);
+        app.add_sibling_item(String::from("x"), String::from("x"), Position::Before);
+        app.add_sibling_item(String::from("y"), String::from("y"), Position::After);
+        assert_eq!(outline(&app), "a\n\tb\n\tx\n\ty\n\tc\n");
+
+        app.undo();
+        app.undo();
+        assert_eq!(*log.borrow(), [
+            "add x at [0, 1]",
+            "add y at [0, 2]",
+            "remove y at [0, 2]",
+            "remove x at [0, 1]",
+        ]);
+        assert_eq!(outline(&app), "a\n\tb\n\tc\n");
+    }
+
+    #[test]
+    fn reports_moves_to_the_top_and_bottom() {
+        let mut app = app("a\nb\nc\nd\n");
+        let log = record_changes(&mut app);
+
+        select(&mut app, 1);
+        app.move_selected_item(|_, _| 0);
+        app.move_selected_item(|_, len| len - 1);
+        assert_eq!(outline(&app), "a\nc\nd\nb\n");
+
+        app.undo();
+        app.undo();
+        assert_eq!(*log.borrow(), [
+            "move [1] to [0]",
+            "move [0] to [3]",
+            "move [3] to [0]",
+            "move [0] to [1]",
+        ]);
+        assert_eq!(outline(&app), "a\nb\nc\nd\n");
+    }
+
+    #[test]
+    fn reports_deleting_the_last_item_of_a_nested_list() {
+        let mut app = app("a\n\tb\nc\n");
+        let log = record_changes(&mut app);
+
+        app.open_selected_item_list();
+        app.delete_selected_item();
+        assert_eq!(app.current, app.root);
+        assert_eq!(outline(&app), "a\nc\n");
+
+        app.undo();
+        assert_eq!(outline(&app), "a\n\tb\nc\n");
+
+        app.redo();
+        assert_eq!(*log.borrow(), [
+            "remove b at [0, 0]",
+            "add b at [0, 0]",
+            "remove b at [0, 0]",
+        ]);
+        assert_eq!(outline(&app), "a\nc\n");
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::ops;
+
+/// A handle to a value in an `Arena`. Every insert hands out a new
+/// generation, so a handle to a removed value never resolves to whatever
+/// later takes its slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Index {
+    slot: usize,
+    generation: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    generation: u64,
+    value: Option<T>,
+}
+
+/// Slot storage that reuses freed slots before growing.
+#[derive(Debug, Clone)]
+pub struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+    generation: u64,
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Arena<T> {
+        Arena{
+            slots: Vec::new(),
+            free: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    pub fn insert(&mut self, value: T) -> Index {
+        self.generation += 1;
+
+        let slot = Slot{generation: self.generation, value: Some(value)};
+        let index = match self.free.pop() {
+            Some(free) => {
+                self.slots[free] = slot;
+                free
+            }
+            None => {
+                self.slots.push(slot);
+                self.slots.len() - 1
+            }
+        };
+
+        Index{slot: index, generation: self.generation}
+    }
+
+    pub fn remove(&mut self, index: Index) -> Option<T> {
+        let slot = self.slots.get_mut(index.slot)?;
+        if slot.generation != index.generation {
+            return None;
+        }
+
+        let value = slot.value.take()?;
+        self.free.push(index.slot);
+
+        Some(value)
+    }
+
+    pub fn get(&self, index: Index) -> Option<&T> {
+        match self.slots.get(index.slot) {
+            Some(slot) if slot.generation == index.generation => slot.value.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, index: Index) -> Option<&mut T> {
+        match self.slots.get_mut(index.slot) {
+            Some(slot) if slot.generation == index.generation => slot.value.as_mut(),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, index: Index) -> bool {
+        self.get(index).is_some()
+    }
+
+    /// Number of slots holding a value.
+    pub fn live_slots(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// Number of slots waiting to be reused.
+    pub fn free_slots(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Index, &T)> {
+        self.slots.iter().enumerate().filter_map(|(slot, entry)| {
+            entry.value.as_ref().map(|value| (Index{slot, generation: entry.generation}, value))
+        })
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|entry| entry.value.as_ref())
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|entry| entry.value.as_mut())
+    }
+
+    /// Moves every live value to the front of the arena and drops the free
+    /// slots. Values get new handles, the returned map goes from old handles
+    /// to new ones.
+    pub fn compact(&mut self) -> HashMap<Index, Index> {
+        let slots = std::mem::take(&mut self.slots);
+        let mut remap = HashMap::new();
+
+        self.free.clear();
+
+        for (slot, entry) in slots.into_iter().enumerate() {
+            if let Some(value) = entry.value {
+                let old = Index{slot, generation: entry.generation};
+                remap.insert(old, self.insert(value));
+            }
+        }
+
+        remap
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Arena<T> {
+        Arena::new()
+    }
+}
+
+impl<T> ops::Index<Index> for Arena<T> {
+    type Output = T;
+
+    fn index(&self, index: Index) -> &T {
+        self.get(index).expect("stale arena index")
+    }
+}
+
+impl<T> ops::IndexMut<Index> for Arena<T> {
+    fn index_mut(&mut self, index: Index) -> &mut T {
+        self.get_mut(index).expect("stale arena index")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_freed_slots_with_a_new_generation() {
+        let mut arena = Arena::new();
+        let a = arena.insert("a");
+        let b = arena.insert("b");
+
+        assert_eq!(arena.remove(a), Some("a"));
+        assert_eq!(arena.free_slots(), 1);
+
+        let c = arena.insert("c");
+        assert_eq!(arena.free_slots(), 0);
+        assert_eq!(arena.live_slots(), 2);
+        assert_eq!(arena[c], "c");
+        assert_eq!(arena[b], "b");
+
+        // the stale handle points at the reused slot but doesn't resolve
+        assert_ne!(a, c);
+        assert!(!arena.contains(a));
+        assert_eq!(arena.get(a), None);
+        assert_eq!(arena.remove(a), None);
+        assert_eq!(arena.live_slots(), 2);
+    }
+
+    #[test]
+    fn removes_a_value_only_once() {
+        let mut arena = Arena::new();
+        let a = arena.insert(1);
+
+        assert_eq!(arena.remove(a), Some(1));
+        assert_eq!(arena.remove(a), None);
+        assert_eq!(arena.free_slots(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "stale arena index")]
+    fn panics_when_indexed_with_a_stale_handle() {
+        let mut arena = Arena::new();
+        let a = arena.insert(1);
+        arena.remove(a);
+
+        let _ = arena[a];
+    }
+
+    #[test]
+    fn compacts_live_values_and_remaps_their_handles() {
+        let mut arena = Arena::new();
+        let handles: Vec<Index> = (0..5).map(|value| arena.insert(value)).collect();
+        arena.remove(handles[0]);
+        arena.remove(handles[3]);
+
+        let remap = arena.compact();
+
+        assert_eq!(arena.free_slots(), 0);
+        assert_eq!(arena.live_slots(), 3);
+        assert_eq!(remap.len(), 3);
+        assert!(!remap.contains_key(&handles[0]));
+        assert!(!remap.contains_key(&handles[3]));
+
+        for value in [1, 2, 4].iter() {
+            let index = remap[&handles[*value as usize]];
+            assert_eq!(arena[index], *value);
+        }
+
+        assert_eq!(arena.values().cloned().collect::<Vec<_>>(), [1, 2, 4]);
+        assert!(handles.iter().all(|handle| !arena.contains(*handle)));
+    }
+
+    #[test]
+    fn iterates_over_live_values_in_slot_order() {
+        let mut arena = Arena::new();
+        let a = arena.insert('a');
+        let b = arena.insert('b');
+        arena.remove(a);
+        let c = arena.insert('c');
+
+        assert_eq!(arena.iter().collect::<Vec<_>>(), [(c, &'c'), (b, &'b')]);
+
+        for value in arena.values_mut() {
+            *value = value.to_ascii_uppercase();
+        }
+        assert_eq!(arena[c], 'C');
+    }
+}
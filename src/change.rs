@@ -0,0 +1,24 @@
+use crate::{Item, Metadata};
+
+/// Where an item is in the tree, the position of each item leading down to
+/// it starting from the root list. `[2, 0]` is the first item in the list
+/// nested below the third item of the root list.
+pub type Path = Vec<usize>;
+
+/// An edit made in the UI, passed to `UI::on_change` as it happens. Paths
+/// are as they were when the change was made, so applying changes in order
+/// keeps a copy of the tree in step. Undo and redo are reported as the
+/// changes that reverse or repeat the original ones.
+#[derive(Debug, Clone)]
+pub enum Change {
+    /// An item, along with any lists nested below it, was inserted at
+    /// `path`.
+    ItemAdded{path: Path, item: Item},
+    /// The item at `path` and everything nested below it was removed.
+    ItemRemoved{path: Path, item: Item},
+    ItemRenamed{path: Path, name: String},
+    /// An item was moved within its list.
+    ItemMoved{from: Path, to: Path},
+    /// The item was checked or unchecked, or its metadata was changed.
+    ItemUpdated{path: Path, done: bool, metadata: Metadata},
+}
@@ -0,0 +1,82 @@
+//! The shape shared by the YAML and TOML formats: nested `items` of
+//! `{id, name, items}` mappings, under an optional list `name`. A nested
+//! list named differently from its item keeps its name in `list_name`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Item, List, Metadata};
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Document {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub items: Vec<Node>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Node {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub done: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata: Metadata,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<Vec<Node>>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl From<Document> for List {
+    fn from(document: Document) -> List {
+        List::new(document.name, document.items.into_iter().map(Item::from).collect())
+    }
+}
+
+impl From<Node> for Item {
+    fn from(node: Node) -> Item {
+        let Node{id, name, done, list_name, metadata, items} = node;
+        let id = id.unwrap_or_else(|| name.clone());
+        let list = items.map(|items| {
+            let list_name = list_name.unwrap_or_else(|| name.clone());
+            List::new(list_name, items.into_iter().map(Item::from).collect())
+        });
+
+        let mut item = Item::new(id, name, list);
+        item.done = done;
+        item.metadata = metadata;
+        item
+    }
+}
+
+impl From<&List> for Document {
+    fn from(list: &List) -> Document {
+        Document{
+            name: list.name.clone(),
+            items: list.items.iter().map(Node::from).collect(),
+        }
+    }
+}
+
+impl From<&Item> for Node {
+    fn from(item: &Item) -> Node {
+        Node{
+            id: Some(item.id.clone()),
+            name: item.name.clone(),
+            done: item.done,
+            list_name: item.list.as_ref()
+                .filter(|list| list.name != item.name)
+                .map(|list| list.name.clone()),
+            metadata: item.metadata.clone(),
+            items: item.list.as_ref().map(|list| list.items.iter().map(Node::from).collect()),
+        }
+    }
+}
@@ -0,0 +1,351 @@
+//! Nested Markdown bullet lists.
+//!
+//! Bullets may use `-`, `*`, `+` or numbers (`1.`, `1)`), indented by any
+//! amount. Task list checkboxes (`- [x]`) map to `Item::done`. Items that
+//! had a checkbox or a number are marked with `checkbox` and `ordered`
+//! metadata fields so they keep them when written back.
+//!
+//! The first `#` heading names the root list. Deeper headings become items
+//! themselves, with the bullets that follow them nested in their list. They
+//! are marked with a `heading` metadata field so they are written back as
+//! headings.
+//!
+//! Anything else, such as paragraphs and fenced code blocks, has no place in
+//! the tree and is left out. `unsupported_lines` finds what would be lost.
+
+use crate::{Item, List};
+
+use super::{indent_width, nest};
+
+// offset between heading levels, so that headings always enclose the
+// bullets below them whatever their indentation
+const HEADING_INDENT: usize = 1 << 16;
+
+// metadata field marking the items that were read from headings
+const HEADING_KEY: &str = "heading";
+// metadata field marking task list items, unchecked ones included
+const CHECKBOX_KEY: &str = "checkbox";
+// metadata field holding the delimiter of a numbered bullet, `.` or `)`
+const ORDERED_KEY: &str = "ordered";
+
+enum Line<'a> {
+    Heading(usize, &'a str),
+    // a bullet with its indentation and, if numbered, its delimiter
+    Bullet(usize, Option<char>, &'a str),
+    Blank,
+    Other,
+}
+
+pub fn from_str(markdown: &str) -> List {
+    let mut name = None;
+    let mut lines = Vec::new();
+    let mut bullet_indent = 0;
+
+    for line in parse_lines(markdown) {
+        match line {
+            Line::Heading(1, text) if name.is_none() => {
+                name = Some(text.to_string());
+            }
+            Line::Heading(level, text) => {
+                let depth = level.max(2) - 2;
+                let (checkbox, text) = parse_checkbox(text);
+                let mut heading = item(text.to_string(), checkbox);
+                heading.metadata.insert(HEADING_KEY.to_string(), String::from("true"));
+
+                lines.push((depth * HEADING_INDENT, heading));
+                bullet_indent = (depth + 1) * HEADING_INDENT;
+            }
+            Line::Bullet(indent, delimiter, text) => {
+                let (checkbox, text) = parse_checkbox(text);
+                let mut bullet = item(text.to_string(), checkbox);
+                if let Some(delimiter) = delimiter {
+                    bullet.metadata.insert(ORDERED_KEY.to_string(), delimiter.to_string());
+                }
+
+                lines.push((bullet_indent + indent, bullet));
+            }
+            Line::Blank | Line::Other => {}
+        }
+    }
+
+    List::new(name.unwrap_or_default(), nest(lines))
+}
+
+/// The lines, starting at 1, that `from_str` leaves out because they are
+/// neither headings nor bullets. Writing the list back drops them.
+pub fn unsupported_lines(markdown: &str) -> Vec<usize> {
+    parse_lines(markdown).iter()
+        .enumerate()
+        .filter(|(_, line)| matches!(line, Line::Other))
+        .map(|(index, _)| index + 1)
+        .collect()
+}
+
+pub fn to_string(list: &List) -> String {
+    let mut markdown = String::new();
+
+    if !list.name.is_empty() {
+        markdown.push_str(&format!("# {}\n\n", list.name));
+    }
+
+    write_section(&mut markdown, &list.items, 2);
+
+    while markdown.ends_with("\n\n") {
+        markdown.pop();
+    }
+
+    markdown
+}
+
+// writes the items of a list below a heading of `level - 1`. The items
+// from the first heading on are all written as headings, since bullets after
+// a heading would be read back into it
+fn write_section(markdown: &mut String, items: &[Item], level: usize) {
+    let first_heading = match level {
+        2..=6 => items.iter().position(is_heading).unwrap_or(items.len()),
+        _ => items.len(),
+    };
+
+    write_items(markdown, &items[..first_heading], 0);
+
+    for item in &items[first_heading..] {
+        if !markdown.is_empty() && !markdown.ends_with("\n\n") {
+            markdown.push('\n');
+        }
+
+        markdown.push_str(&format!("{} {}{}\n\n", "#".repeat(level), checkbox(item), item.name));
+
+        if let Some(list) = &item.list {
+            write_section(markdown, &list.items, level + 1);
+        }
+    }
+}
+
+// numbers each run of numbered bullets from 1. Nested bullets are indented
+// to line up with the text of their parent
+fn write_items(markdown: &mut String, items: &[Item], indent: usize) {
+    let mut number = 0;
+    let mut previous = None;
+
+    for item in items {
+        let delimiter = item.metadata.get(ORDERED_KEY);
+        number = if delimiter.is_some() && delimiter == previous { number + 1 } else { 1 };
+        previous = delimiter;
+
+        let bullet = match delimiter {
+            Some(delimiter) => format!("{}{}", number, delimiter),
+            None => String::from("-"),
+        };
+        markdown.push_str(&format!("{}{} {}{}\n", " ".repeat(indent), bullet, checkbox(item), item.name));
+
+        if let Some(list) = &item.list {
+            write_items(markdown, &list.items, indent + bullet.len() + 1);
+        }
+    }
+}
+
+fn checkbox(item: &Item) -> &'static str {
+    if item.done {
+        "[x] "
+    } else if item.metadata.contains_key(CHECKBOX_KEY) {
+        "[ ] "
+    } else {
+        ""
+    }
+}
+
+fn is_heading(item: &Item) -> bool {
+    item.metadata.contains_key(HEADING_KEY)
+}
+
+// `checkbox` is whether the item was checked, if it had a checkbox at all
+fn item(name: String, checkbox: Option<bool>) -> Item {
+    let mut item = Item::new(name.clone(), name, None);
+    if let Some(done) = checkbox {
+        item.done = done;
+        item.metadata.insert(CHECKBOX_KEY.to_string(), String::from("true"));
+    }
+    item
+}
+
+fn parse_lines(markdown: &str) -> Vec<Line<'_>> {
+    // the marker that opened the code block the line is in
+    let mut fence: Option<&str> = None;
+
+    markdown.lines().map(|line| {
+        let trimmed = line.trim_start();
+
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            return Line::Other;
+        }
+
+        if let Some(marker) = ["```", "~~~"].iter().find(|marker| trimmed.starts_with(*marker)) {
+            fence = Some(marker);
+            Line::Other
+        } else if trimmed.is_empty() {
+            Line::Blank
+        } else if let Some((level, text)) = parse_heading(trimmed) {
+            Line::Heading(level, text)
+        } else if let Some((delimiter, text)) = parse_bullet(trimmed) {
+            Line::Bullet(indent_width(line), delimiter, text)
+        } else {
+            Line::Other
+        }
+    }).collect()
+}
+
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    let text = &line[level..];
+    if !text.is_empty() && !text.starts_with(' ') {
+        return None;
+    }
+
+    Some((level, text.trim().trim_end_matches('#').trim_end()))
+}
+
+fn parse_bullet(line: &str) -> Option<(Option<char>, &str)> {
+    for marker in ["- ", "* ", "+ "].iter() {
+        if let Some(text) = line.strip_prefix(marker) {
+            return Some((None, text.trim()));
+        }
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &line[digits..];
+        for delimiter in ['.', ')'].iter() {
+            if let Some(text) = rest.strip_prefix(*delimiter).and_then(|text| text.strip_prefix(' ')) {
+                return Some((Some(*delimiter), text.trim()));
+            }
+        }
+    }
+
+    None
+}
+
+fn parse_checkbox(text: &str) -> (Option<bool>, &str) {
+    if let Some(rest) = text.strip_prefix("[ ] ") {
+        (Some(false), rest.trim())
+    } else if let Some(rest) = text.strip_prefix("[x] ").or_else(|| text.strip_prefix("[X] ")) {
+        (Some(true), rest.trim())
+    } else {
+        (None, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(items: &[Item]) -> Vec<&str> {
+        items.iter().map(|item| item.name.as_str()).collect()
+    }
+
+    fn nested(item: &Item) -> &[Item] {
+        item.list.as_ref().map(|list| list.items.as_slice()).unwrap_or(&[])
+    }
+
+    #[test]
+    fn reads_nested_bullets_and_checkboxes() {
+        let list = from_str("# Tasks\n\n- a\n  * [x] b\n    1. c\n+ [ ] d\n");
+
+        assert_eq!(list.name, "Tasks");
+        assert_eq!(names(&list.items), ["a", "d"]);
+        assert_eq!(names(nested(&list.items[0])), ["b"]);
+        assert!(nested(&list.items[0])[0].done);
+        assert_eq!(names(nested(&nested(&list.items[0])[0])), ["c"]);
+        assert!(!list.items[1].done);
+    }
+
+    #[test]
+    fn nests_bullets_after_a_line_indented_less_than_the_first() {
+        let list = from_str("  - a\n- b\n  - c\n");
+
+        assert_eq!(names(&list.items), ["a", "b"]);
+        assert_eq!(names(nested(&list.items[1])), ["c"]);
+    }
+
+    #[test]
+    fn nests_bullets_under_headings() {
+        let list = from_str("# Notes\n\n- loose\n\n## Work\n\n- a\n\n### Later\n\n- b\n\n## Home\n");
+
+        assert_eq!(names(&list.items), ["loose", "Work", "Home"]);
+        assert_eq!(names(nested(&list.items[1])), ["a", "Later"]);
+        assert_eq!(names(nested(&nested(&list.items[1])[1])), ["b"]);
+        assert!(is_heading(&list.items[1]));
+        assert!(!is_heading(&list.items[0]));
+    }
+
+    #[test]
+    fn skips_fenced_code_blocks() {
+        let list = from_str("- a\n```\n- not an item\n# not a heading\n```\n~~~rust\n- nor this\n~~~\n- b\n");
+
+        assert_eq!(names(&list.items), ["a", "b"]);
+    }
+
+    #[test]
+    fn finds_lines_that_are_not_headings_or_bullets() {
+        let markdown = "# Notes\n\nSome prose.\n\n- a\n```\n- code\n```\n- b\n";
+
+        assert_eq!(unsupported_lines(markdown), [3, 6, 7, 8]);
+        assert!(unsupported_lines("# Notes\n\n- a\n\n## b\n").is_empty());
+    }
+
+    #[test]
+    fn writes_headings_back_as_headings() {
+        let markdown = "# Notes\n\n- loose\n  - [x] nested\n\n## Work\n\n- a\n\n### [x] Later\n\n- b\n\n## Home\n";
+
+        assert_eq!(to_string(&from_str(markdown)), markdown);
+    }
+
+    #[test]
+    fn writes_items_after_a_heading_as_headings() {
+        let mut list = from_str("## Work\n");
+        list.items.push(item(String::from("Home"), None));
+
+        assert_eq!(to_string(&list), "## Work\n\n## Home\n");
+        assert_eq!(names(&from_str(&to_string(&list)).items), ["Work", "Home"]);
+    }
+
+    #[test]
+    fn writes_bullets_below_the_deepest_heading_level() {
+        let markdown = "## 2\n\n### 3\n\n#### 4\n\n##### 5\n\n###### 6\n\n";
+        let mut list = from_str(markdown);
+
+        let mut level_6 = &mut list.items[0];
+        for _ in 0..4 {
+            level_6 = &mut level_6.list.as_mut().unwrap().items[0];
+        }
+        let mut heading = item(String::from("7"), None);
+        heading.metadata.insert(HEADING_KEY.to_string(), String::from("true"));
+        level_6.list = Some(List::new(String::from("6"), vec![heading]));
+
+        assert_eq!(to_string(&list), format!("{}- 7\n", markdown));
+    }
+
+    #[test]
+    fn writes_checkboxes_and_numbers_back() {
+        let markdown = "# Tasks\n\n- [ ] buy\n- [x] sell\n- note\n1. first\n2. [ ] second\n   - [ ] nested\n1) third\n\n## [ ] Later\n";
+        let list = from_str(markdown);
+
+        assert!(!list.items[0].done);
+        assert!(list.items[1].done);
+        assert_eq!(to_string(&list), markdown);
+    }
+
+    #[test]
+    fn renumbers_numbered_bullets() {
+        let mut list = from_str("1. a\n2. b\n3. c\n");
+        list.items.remove(0);
+
+        assert_eq!(to_string(&list), "1. b\n2. c\n");
+    }
+}
@@ -0,0 +1,170 @@
+//! Conversions between `List` and other outline formats.
+
+use std::error;
+use std::fmt;
+
+pub mod markdown;
+pub mod text;
+#[cfg(feature = "opml")]
+pub mod opml;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+#[cfg(feature = "toml")]
+pub mod toml;
+#[cfg(any(feature = "yaml", feature = "toml"))]
+mod document;
+
+use crate::{Item, List};
+
+/// A document that couldn't be read into a `List`, or a `List` that couldn't
+/// be written out.
+#[derive(Debug, Clone)]
+pub struct Error {
+    /// Line and column in the document where the problem is, both starting
+    /// at 1. Errors from writing a list have none.
+    pub location: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl Error {
+    /// Creates an error that isn't tied to a place in a document.
+    pub fn new(message: String) -> Error {
+        Error{location: None, message}
+    }
+
+    /// Creates an error for the given byte offset into `text`.
+    pub fn at_offset(text: &str, offset: usize, message: String) -> Error {
+        let before = &text[..floor_char_boundary(text, offset)];
+        let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+
+        let line = before.matches('\n').count() + 1;
+        let column = before[line_start..].chars().count() + 1;
+
+        Error{location: Some((line, column)), message}
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.location {
+            Some((line, column)) => write!(f, "{} at line {}, column {}", self.message, line, column),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+fn floor_char_boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// Builds nested lists out of items paired with their indentation. An item
+/// indented further than the one before it starts a list under that item,
+/// named after it. Indent widths don't have to be consistent, an item
+/// belongs to the closest less indented item above it.
+fn nest(lines: Vec<(usize, Item)>) -> Vec<Item> {
+    let base = lines.first().map(|(indent, _)| *indent).unwrap_or(0);
+    let mut stack: Vec<(usize, Vec<Item>)> = vec![(base, Vec::new())];
+
+    for (indent, item) in lines {
+        while stack.len() > 1 && indent < stack[stack.len() - 1].0 {
+            close_level(&mut stack);
+        }
+
+        // a line indented less than the first one is still at the top
+        // level, and the lines after it nest relative to it
+        if stack.len() == 1 && indent < stack[0].0 {
+            stack[0].0 = indent;
+        }
+
+        let (level_indent, items) = stack.last_mut().unwrap();
+        if indent > *level_indent && !items.is_empty() {
+            stack.push((indent, vec![item]));
+        } else {
+            items.push(item);
+        }
+    }
+
+    while stack.len() > 1 {
+        close_level(&mut stack);
+    }
+
+    stack.pop().map(|(_, items)| items).unwrap_or_default()
+}
+
+// moves the innermost level's items into the list of the item above it
+fn close_level(stack: &mut Vec<(usize, Vec<Item>)>) {
+    let (_, items) = stack.pop().unwrap();
+    let parent = stack.last_mut().unwrap().1.last_mut().unwrap();
+
+    match parent.list.as_mut() {
+        Some(list) => list.items.extend(items),
+        None => parent.list = Some(List::new(parent.name.clone(), items)),
+    }
+}
+
+/// Width of the leading whitespace of a line, counting tabs as 4 columns.
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nest_names(lines: &[(usize, &str)]) -> String {
+        let lines = lines.iter()
+            .map(|(indent, name)| (*indent, Item::new(name.to_string(), name.to_string(), None)))
+            .collect();
+
+        text::to_string(&List::new(String::new(), nest(lines)))
+    }
+
+    #[test]
+    fn nests_lines_under_the_less_indented_line_above() {
+        assert_eq!(nest_names(&[(0, "a"), (2, "b"), (4, "c"), (2, "d"), (0, "e")]), "a\n\tb\n\t\tc\n\td\ne\n");
+    }
+
+    #[test]
+    fn allows_inconsistent_indent_widths() {
+        assert_eq!(nest_names(&[(0, "a"), (4, "b"), (2, "c"), (0, "d")]), "a\n\tb\n\tc\nd\n");
+    }
+
+    #[test]
+    fn measures_nesting_from_a_line_indented_less_than_the_first() {
+        assert_eq!(nest_names(&[(4, "a"), (0, "b"), (2, "c")]), "a\nb\n\tc\n");
+        assert_eq!(nest_names(&[(2, "a"), (4, "b"), (0, "c"), (2, "d")]), "a\n\tb\nc\n\td\n");
+    }
+
+    #[test]
+    fn names_nested_lists_after_their_item() {
+        let items = nest(vec![
+            (0, Item::new(String::from("a"), String::from("a"), None)),
+            (1, Item::new(String::from("b"), String::from("b"), None)),
+        ]);
+
+        assert_eq!(items[0].list.as_ref().map(|list| list.name.as_str()), Some("a"));
+    }
+
+    #[test]
+    fn nests_nothing_into_an_empty_list() {
+        assert!(nest(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn reports_lines_and_columns_from_byte_offsets() {
+        let error = Error::at_offset("ab\ncdé\nf", 7, String::from("bad"));
+
+        assert_eq!(error.location, Some((2, 4)));
+        assert_eq!(error.to_string(), "bad at line 2, column 4");
+        assert_eq!(Error::new(String::from("bad")).to_string(), "bad");
+    }
+}
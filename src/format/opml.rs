@@ -0,0 +1,320 @@
+//! OPML outlines.
+//!
+//! Each `<outline>` element is an item, its `text` attribute the item name
+//! and its child outlines the item's list. The `id` attribute holds
+//! `Item::id` and `_complete="true"` marks an item done. A nested list
+//! named differently from its item keeps its name in `_list`. Any other
+//! attribute is kept in `Item::metadata`.
+//!
+//! Metadata keys that can't be attribute names, or that clash with the
+//! attributes above, are written as a pair of `_key-1="due date"` and
+//! `_value-1="friday"` attributes, numbered within the outline.
+
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::{Item, List};
+
+use super::Error;
+
+const RESERVED_ATTRIBUTES: [&str; 4] = ["text", "id", "_complete", "_list"];
+const KEY_PREFIX: &str = "_key-";
+const VALUE_PREFIX: &str = "_value-";
+
+// an outline element whose closing tag hasn't been read yet
+struct Outline {
+    item: Item,
+    list_name: Option<String>,
+    items: Vec<Item>,
+}
+
+pub fn from_str(opml: &str) -> Result<List, Error> {
+    let mut reader = Reader::from_str(opml);
+    reader.trim_text(true);
+
+    let error = |reader: &Reader<&[u8]>, message: String| {
+        Error::at_offset(opml, reader.buffer_position(), message)
+    };
+
+    let mut name = String::new();
+    let mut items = Vec::new();
+    let mut open: Vec<Outline> = Vec::new();
+    let mut in_title = false;
+    let mut found_root = false;
+
+    loop {
+        let event = reader.read_event().map_err(|err| error(&reader, err.to_string()))?;
+
+        match event {
+            Event::Start(element) | Event::Empty(element) if !found_root => {
+                if element.name().as_ref() != b"opml" {
+                    return Err(error(&reader, String::from("expected an <opml> document")));
+                }
+
+                found_root = true;
+            }
+            Event::Start(element) => match element.name().as_ref() {
+                b"outline" => {
+                    let outline = read_outline(&element).map_err(|message| error(&reader, message))?;
+                    open.push(outline);
+                }
+                b"title" => in_title = true,
+                _ => {}
+            },
+            Event::Empty(element) if element.name().as_ref() == b"outline" => {
+                let outline = read_outline(&element).map_err(|message| error(&reader, message))?;
+                close_outline(&mut open, &mut items, outline);
+            }
+            Event::Text(text) if in_title => {
+                name = text.unescape().map_err(|err| error(&reader, err.to_string()))?.into_owned();
+            }
+            Event::End(element) => match element.name().as_ref() {
+                b"outline" => {
+                    if let Some(outline) = open.pop() {
+                        close_outline(&mut open, &mut items, outline);
+                    }
+                }
+                b"title" => in_title = false,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !found_root {
+        return Err(error(&reader, String::from("expected an <opml> document")));
+    }
+
+    if !open.is_empty() {
+        return Err(error(&reader, String::from("unclosed <outline> element")));
+    }
+
+    Ok(List::new(name, items))
+}
+
+pub fn to_string(list: &List) -> String {
+    let mut opml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    opml.push_str("<opml version=\"2.0\">\n");
+    opml.push_str("  <head>\n");
+    opml.push_str(&format!("    <title>{}</title>\n", escape(&list.name)));
+    opml.push_str("  </head>\n");
+    opml.push_str("  <body>\n");
+    write_outlines(&mut opml, &list.items, 2);
+    opml.push_str("  </body>\n");
+    opml.push_str("</opml>\n");
+
+    opml
+}
+
+fn write_outlines(opml: &mut String, items: &[Item], depth: usize) {
+    let indent = "  ".repeat(depth);
+
+    for item in items {
+        opml.push_str(&format!("{}<outline text=\"{}\" id=\"{}\"", indent, escape(&item.name), escape(&item.id)));
+
+        if item.done {
+            opml.push_str(" _complete=\"true\"");
+        }
+
+        let mut pairs = 0;
+        for (key, value) in item.metadata.iter() {
+            if is_metadata_attribute(key) {
+                opml.push_str(&format!(" {}=\"{}\"", key, escape(value)));
+            } else {
+                pairs += 1;
+                opml.push_str(&format!(" {}{}=\"{}\"", KEY_PREFIX, pairs, escape(key)));
+                opml.push_str(&format!(" {}{}=\"{}\"", VALUE_PREFIX, pairs, escape(value)));
+            }
+        }
+
+        match &item.list {
+            Some(list) => {
+                if list.name != item.name {
+                    opml.push_str(&format!(" _list=\"{}\"", escape(&list.name)));
+                }
+
+                opml.push_str(">\n");
+                write_outlines(opml, &list.items, depth + 1);
+                opml.push_str(&format!("{}</outline>\n", indent));
+            }
+            None => opml.push_str("/>\n"),
+        }
+    }
+}
+
+fn read_outline(element: &BytesStart) -> Result<Outline, String> {
+    let mut name = String::new();
+    let mut id = None;
+    let mut outline = Outline{
+        item: Item::new(String::new(), String::new(), None),
+        list_name: None,
+        items: Vec::new(),
+    };
+
+    // a `_key-` attribute waiting for its `_value-`, with its number
+    let mut pair_key: Option<(String, String)> = None;
+
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|err| err.to_string())?;
+        let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+        let value = attribute.unescape_value().map_err(|err| err.to_string())?.into_owned();
+
+        match key.as_str() {
+            "text" => name = value,
+            "id" => id = Some(value),
+            "_complete" => outline.item.done = value == "true",
+            "_list" => outline.list_name = Some(value),
+            _ if key.starts_with(KEY_PREFIX) => {
+                if let Some((number, _)) = pair_key.take() {
+                    return Err(format!("{}{} has no {}{}", KEY_PREFIX, number, VALUE_PREFIX, number));
+                }
+                pair_key = Some((key[KEY_PREFIX.len()..].to_string(), value));
+            }
+            _ if key.starts_with(VALUE_PREFIX) => {
+                let number = &key[VALUE_PREFIX.len()..];
+                match pair_key.take() {
+                    Some((key_number, pair_key)) if key_number == number => {
+                        outline.item.metadata.insert(pair_key, value);
+                    }
+                    _ => return Err(format!("{} has no {}{} before it", key, KEY_PREFIX, number)),
+                }
+            }
+            _ => {
+                outline.item.metadata.insert(key, value);
+            }
+        }
+    }
+
+    if let Some((number, _)) = pair_key {
+        return Err(format!("{}{} has no {}{}", KEY_PREFIX, number, VALUE_PREFIX, number));
+    }
+
+    outline.item.id = id.unwrap_or_else(|| name.clone());
+    outline.item.name = name;
+
+    Ok(outline)
+}
+
+// attaches a finished outline to the outline it's nested in, or to the
+// top level when it isn't nested
+fn close_outline(open: &mut [Outline], items: &mut Vec<Item>, outline: Outline) {
+    let mut item = outline.item;
+
+    if !outline.items.is_empty() {
+        let list_name = outline.list_name.unwrap_or_else(|| item.name.clone());
+        item.list = Some(List::new(list_name, outline.items));
+    }
+
+    match open.last_mut() {
+        Some(parent) => parent.items.push(item),
+        None => items.push(item),
+    }
+}
+
+// whether a metadata key can be written as an attribute of its own
+fn is_metadata_attribute(key: &str) -> bool {
+    is_attribute_name(key)
+        && !RESERVED_ATTRIBUTES.contains(&key)
+        && !key.starts_with(KEY_PREFIX)
+        && !key.starts_with(VALUE_PREFIX)
+}
+
+fn is_attribute_name(key: &str) -> bool {
+    let mut chars = key.chars();
+
+    match chars.next() {
+        Some(first) if first.is_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, list: Option<List>) -> Item {
+        Item::new(name.to_string(), name.to_string(), list)
+    }
+
+    #[test]
+    fn reads_nested_outlines() {
+        let list = from_str(r#"<?xml version="1.0"?>
+<opml version="2.0">
+  <head><title>Trip &amp; plans</title></head>
+  <body>
+    <outline text="Pack" _list="Packing list">
+      <outline text="Socks" id="socks" _complete="true" color="blue"/>
+    </outline>
+    <outline text="Go"/>
+  </body>
+</opml>"#).unwrap();
+
+        assert_eq!(list.name, "Trip & plans");
+        assert_eq!(list.items.len(), 2);
+        assert_eq!(list.items[0].id, "Pack");
+
+        let nested = list.items[0].list.as_ref().unwrap();
+        assert_eq!(nested.name, "Packing list");
+        assert_eq!(nested.items[0].id, "socks");
+        assert!(nested.items[0].done);
+        assert_eq!(nested.items[0].metadata.get("color").map(String::as_str), Some("blue"));
+        assert!(list.items[1].list.is_none());
+    }
+
+    #[test]
+    fn round_trips_a_list() {
+        let mut socks = item("Socks \"wool\" <2>", None);
+        socks.done = true;
+        socks.metadata.insert(String::from("status"), String::from("open"));
+        socks.metadata.insert(String::from("owner"), String::from("me & you"));
+        let list = List::new(String::from("Trip"), vec![
+            item("Pack", Some(List::new(String::from("Packing"), vec![socks]))),
+            item("Go", Some(List::new(String::from("Go"), vec![item("Now", None)]))),
+        ]);
+
+        let opml = to_string(&list);
+        assert_eq!(to_string(&from_str(&opml).unwrap()), opml);
+
+        let read = from_str(&opml).unwrap();
+        let socks = &read.items[0].list.as_ref().unwrap().items[0];
+        assert_eq!(socks.name, "Socks \"wool\" <2>");
+        assert!(socks.done);
+        assert_eq!(socks.metadata.keys().collect::<Vec<_>>(), ["status", "owner"]);
+        assert_eq!(read.items[0].list.as_ref().unwrap().name, "Packing");
+    }
+
+    #[test]
+    fn keeps_metadata_keys_that_are_not_attribute_names() {
+        let mut task = item("Task", None);
+        for key in ["due date", "text", "_complete", "_key-1", "9lives", "owner"].iter() {
+            task.metadata.insert(key.to_string(), format!("{} value", key));
+        }
+        let list = List::new(String::from("Tasks"), vec![task]);
+
+        let opml = to_string(&list);
+        assert!(opml.contains(r#"_key-1="due date" _value-1="due date value""#));
+        assert!(opml.contains(r#"owner="owner value""#));
+
+        let read = from_str(&opml).unwrap();
+        assert_eq!(read.items[0].name, "Task");
+        assert!(!read.items[0].done);
+        assert_eq!(read.items[0].metadata, list.items[0].metadata);
+    }
+
+    #[test]
+    fn reports_where_a_document_is_broken() {
+        let error = from_str("<opml>\n  <body>\n    <outline text=\"a\">\n  </body>\n</opml>").unwrap_err();
+        assert_eq!(error.location.map(|(line, _)| line), Some(4));
+
+        let error = from_str("<opml>\n<body>\n<outline _key-1=\"a b\"/>\n</body>\n</opml>").unwrap_err();
+        assert_eq!(error.location.map(|(line, _)| line), Some(3));
+        assert!(error.message.contains("_value-1"));
+
+        let error = from_str("<html></html>").unwrap_err();
+        assert_eq!(error.location, Some((1, 7)));
+    }
+}
@@ -0,0 +1,65 @@
+//! Indented plain text, one item per line.
+//!
+//! Lines can be indented with tabs or spaces, by any amount. The item name
+//! doubles as its id, and nested lists are named after their item. Written
+//! text is indented with tabs.
+
+use crate::{Item, List};
+
+use super::{indent_width, nest};
+
+/// Reads items from indented text. Plain text has no place for a list
+/// name, so the returned list is unnamed.
+pub fn from_str(text: &str) -> List {
+    let lines = text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let name = line.trim().to_string();
+            (indent_width(line), Item::new(name.clone(), name, None))
+        })
+        .collect();
+
+    List::new(String::new(), nest(lines))
+}
+
+pub fn to_string(list: &List) -> String {
+    let mut text = String::new();
+    write_items(&mut text, &list.items, 0);
+
+    text
+}
+
+fn write_items(text: &mut String, items: &[Item], depth: usize) {
+    for item in items {
+        text.push_str(&format!("{}{}\n", "\t".repeat(depth), item.name));
+
+        if let Some(list) = &item.list {
+            write_items(text, &list.items, depth + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_items_nested_by_tabs_or_spaces() {
+        let list = from_str("a\n\tb\n    c\n\n  d\ne\n");
+
+        assert_eq!(list.name, "");
+        assert_eq!(list.items.len(), 2);
+        let nested = &list.items[0].list.as_ref().unwrap();
+        assert_eq!(nested.name, "a");
+        assert_eq!(nested.items.iter().map(|item| item.name.as_str()).collect::<Vec<_>>(), ["b", "c", "d"]);
+        assert_eq!(list.items[1].id, "e");
+    }
+
+    #[test]
+    fn round_trips_a_list() {
+        let text = "a\n\tb\n\t\tc\n\td\ne\n";
+
+        assert_eq!(to_string(&from_str(text)), text);
+        assert_eq!(to_string(&from_str("    a\n      b\n  c\n")), "a\n\tb\nc\n");
+    }
+}
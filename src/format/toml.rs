@@ -0,0 +1,69 @@
+//! TOML documents, a table with a list `name` and an array of `items`
+//! tables. Each item has an `id`, a `name` and optionally nested `items`,
+//! `done`, `metadata` and `list_name`.
+
+use crate::List;
+
+use super::document::Document;
+use super::Error;
+
+pub fn from_str(toml: &str) -> Result<List, Error> {
+    let document: Document = ::toml::from_str(toml).map_err(|err| {
+        let offset = err.span().map(|span| span.start).unwrap_or(0);
+        Error::at_offset(toml, offset, err.message().to_string())
+    })?;
+
+    Ok(List::from(document))
+}
+
+pub fn to_string(list: &List) -> Result<String, Error> {
+    ::toml::to_string(&Document::from(list)).map_err(|err| Error::new(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Item;
+
+    #[test]
+    fn round_trips_a_list() {
+        let mut apples = Item::new(String::from("apples"), String::from("Apples"), None);
+        apples.done = true;
+        apples.metadata.insert(String::from("store"), String::from("market"));
+        apples.metadata.insert(String::from("aisle"), String::from("4"));
+        let list = List::new(String::from("groceries"), vec![
+            Item::new(String::from("fruit"), String::from("Fruit"), Some(List::new(String::from("Fruit to buy"), vec![apples]))),
+            Item::new(String::from("Bread"), String::from("Bread"), None),
+        ]);
+
+        let toml = to_string(&list).unwrap();
+        assert!(toml.contains("list_name = \"Fruit to buy\""));
+
+        let read = from_str(&toml).unwrap();
+        assert_eq!(to_string(&read).unwrap(), toml);
+        assert_eq!(read.name, "groceries");
+        let nested = read.items[0].list.as_ref().unwrap();
+        assert_eq!(nested.name, "Fruit to buy");
+        assert!(nested.items[0].done);
+        assert_eq!(nested.items[0].metadata.keys().collect::<Vec<_>>(), ["store", "aisle"]);
+        assert!(read.items[1].list.is_none());
+    }
+
+    #[test]
+    fn names_nested_lists_after_their_item_by_default() {
+        let list = from_str("[[items]]\nname = \"Fruit\"\n\n[[items.items]]\nname = \"Apples\"\n").unwrap();
+
+        assert_eq!(list.items[0].list.as_ref().map(|list| list.name.as_str()), Some("Fruit"));
+        assert_eq!(list.items[0].list.as_ref().unwrap().items[0].id, "Apples");
+    }
+
+    #[test]
+    fn reports_where_a_document_is_broken() {
+        let error = from_str("name = \"groceries\"\n\n[[items]]\nname = \"Fruit\"\ncolour = \"red\"\n").unwrap_err();
+        assert_eq!(error.location, Some((5, 1)));
+        assert!(error.message.contains("colour"));
+
+        let error = from_str("name = \"groceries\"\nitems = [\n").unwrap_err();
+        assert_eq!(error.location.map(|(line, _)| line), Some(3));
+    }
+}
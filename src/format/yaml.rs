@@ -0,0 +1,94 @@
+//! YAML documents, either a mapping with a list `name` and its `items`, or
+//! just the sequence of items. Each item is a mapping of `id`, `name` and
+//! optionally nested `items`, `done`, `metadata` and `list_name`.
+
+use crate::List;
+
+use super::document::{Document, Node};
+use super::Error;
+
+pub fn from_str(yaml: &str) -> Result<List, Error> {
+    let value: serde_yaml::Value = serde_yaml::from_str(yaml).map_err(|err| error(yaml, err))?;
+
+    let document = if value.is_sequence() {
+        let items: Vec<Node> = serde_yaml::from_str(yaml).map_err(|err| error(yaml, err))?;
+        Document{name: String::new(), items}
+    } else {
+        serde_yaml::from_str(yaml).map_err(|err| error(yaml, err))?
+    };
+
+    Ok(List::from(document))
+}
+
+pub fn to_string(list: &List) -> Result<String, Error> {
+    serde_yaml::to_string(&Document::from(list)).map_err(|err| Error::new(err.to_string()))
+}
+
+fn error(yaml: &str, err: serde_yaml::Error) -> Error {
+    let message = err.to_string();
+    // the message repeats the location, which Error already reports
+    let message = match message.rfind(" at line ") {
+        Some(index) => message[..index].to_string(),
+        None => message,
+    };
+
+    match err.location() {
+        Some(location) => Error::at_offset(yaml, location.index(), message),
+        None => Error::new(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Item;
+
+    #[test]
+    fn reads_a_named_list_or_a_bare_sequence() {
+        let list = from_str("name: groceries\nitems:\n  - name: Fruit\n    items:\n      - id: apples\n        name: Apples\n        done: true\n").unwrap();
+
+        assert_eq!(list.name, "groceries");
+        assert_eq!(list.items[0].id, "Fruit");
+        let nested = list.items[0].list.as_ref().unwrap();
+        assert_eq!(nested.name, "Fruit");
+        assert_eq!(nested.items[0].id, "apples");
+        assert!(nested.items[0].done);
+
+        let list = from_str("- name: a\n- name: b\n").unwrap();
+        assert_eq!(list.name, "");
+        assert_eq!(list.items.len(), 2);
+    }
+
+    #[test]
+    fn round_trips_a_list() {
+        let mut apples = Item::new(String::from("apples"), String::from("Apples"), None);
+        apples.done = true;
+        apples.metadata.insert(String::from("store"), String::from("market"));
+        apples.metadata.insert(String::from("aisle"), String::from("4"));
+        let list = List::new(String::from("groceries"), vec![
+            Item::new(String::from("fruit"), String::from("Fruit"), Some(List::new(String::from("Fruit to buy"), vec![apples]))),
+            Item::new(String::from("bread"), String::from("Bread"), Some(List::new(String::from("Bread"), Vec::new()))),
+        ]);
+
+        let yaml = to_string(&list).unwrap();
+        assert!(yaml.contains("list_name: Fruit to buy"));
+        assert_eq!(yaml.matches("list_name").count(), 1);
+
+        let read = from_str(&yaml).unwrap();
+        assert_eq!(to_string(&read).unwrap(), yaml);
+        let nested = read.items[0].list.as_ref().unwrap();
+        assert_eq!(nested.name, "Fruit to buy");
+        assert_eq!(nested.items[0].metadata.keys().collect::<Vec<_>>(), ["store", "aisle"]);
+        assert_eq!(read.items[1].list.as_ref().map(|list| list.items.len()), Some(0));
+    }
+
+    #[test]
+    fn reports_where_a_document_is_broken() {
+        let error = from_str("name: groceries\nitems:\n  - name: Fruit\n    colour: red\n").unwrap_err();
+        assert_eq!(error.location, Some((4, 5)));
+        assert!(error.message.contains("colour"));
+
+        let error = from_str("items:\n  - name: [a\n").unwrap_err();
+        assert_eq!(error.location.map(|(line, _)| line), Some(3));
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+
+use crate::arena::{Arena, Index};
+use crate::change::Change;
+use crate::list::{List, Item};
+
+/// The state of the App before or after a change, labelled with a short
+/// description of that change.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub description: String,
+    pub lists: Arena<List>,
+    pub root: Index,
+    pub current: Index,
+    pub depth: usize,
+    pub clipboard: Option<Item>,
+    pub revision: u64,
+    // what the change did, each paired with the change that reverses it
+    pub changes: Vec<(Change, Change)>,
+}
+
+pub struct History {
+    undo: VecDeque<Snapshot>,
+    redo: Vec<Snapshot>,
+    depth: usize,
+}
+
+impl History {
+    pub fn new(depth: usize) -> History {
+        History{
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            depth,
+        }
+    }
+
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        self.truncate();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Records the state from before a change. Anything that could be redone
+    /// is dropped.
+    pub fn record(&mut self, snapshot: Snapshot) {
+        self.undo.push_back(snapshot);
+        self.redo.clear();
+        self.truncate();
+    }
+
+    /// Adds to the changes made since the last recorded state.
+    pub fn record_change(&mut self, change: Change, inverse: Change) {
+        if let Some(snapshot) = self.undo.back_mut() {
+            snapshot.changes.push((change, inverse));
+        }
+    }
+
+    /// Returns the state to go back to, keeping `current` so it can be redone.
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let previous = self.undo.pop_back()?;
+        self.redo.push(Snapshot{
+            description: previous.description.clone(),
+            changes: previous.changes.clone(),
+            ..current
+        });
+
+        Some(previous)
+    }
+
+    /// Returns the state to go forward to, keeping `current` so it can be
+    /// undone again.
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let next = self.redo.pop()?;
+        self.undo.push_back(Snapshot{
+            description: next.description.clone(),
+            changes: next.changes.clone(),
+            ..current
+        });
+
+        Some(next)
+    }
+
+    fn truncate(&mut self) {
+        while self.undo.len() > self.depth {
+            self.undo.pop_front();
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::cell::Cell;
+
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+
+/// Produces the id for a newly added item from the name it was given.
+pub type Generator = Box<dyn Fn(&str) -> String>;
+
+/// Uses the item name as its id.
+pub fn name() -> Generator {
+    Box::new(|name: &str| name.to_string())
+}
+
+/// Random alphanumeric ids of the given length.
+pub fn random(length: usize) -> Generator {
+    Box::new(move |_: &str| {
+        thread_rng().sample_iter(&Alphanumeric).take(length).collect()
+    })
+}
+
+/// Increasing numeric ids, starting at `start`.
+pub fn sequential(start: u64) -> Generator {
+    let next = Cell::new(start);
+
+    Box::new(move |_: &str| {
+        let id = next.get();
+        next.set(id + 1);
+        id.to_string()
+    })
+}
+
+/// Lowercase, dash separated ids derived from the item name, e.g.
+/// "Buy more milk!" becomes "buy-more-milk".
+pub fn slug() -> Generator {
+    Box::new(|name: &str| slugify(name))
+}
+
+fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugifies_names() {
+        assert_eq!(slugify("Buy more milk!"), "buy-more-milk");
+        assert_eq!(slugify("  --Hello,   World--  "), "hello-world");
+        assert_eq!(slugify("Crème Brûlée 2"), "crème-brûlée-2");
+        assert_eq!(slugify("!!!"), "");
+    }
+
+    #[test]
+    fn counts_up_sequential_ids() {
+        let generate = sequential(7);
+
+        assert_eq!(generate("a"), "7");
+        assert_eq!(generate("b"), "8");
+    }
+}
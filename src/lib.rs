@@ -1,15 +1,31 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 mod util;
+mod arena;
 mod list;
 mod app;
+mod history;
+mod search;
 pub mod options;
+pub mod id;
+pub mod format;
+pub mod notification;
+pub mod change;
 
 use crate::app::{App};
-pub use crate::options::{PageOptions};
+use crate::arena::{Arena, Index};
+pub use crate::options::{PageOptions, ViewMode};
+pub use crate::notification::{Notification};
+pub use crate::change::{Change};
+pub use crate::list::{Metadata};
 use crate::list::{List as InternList, Item as InternItem};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct List {
     pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub items: Vec<Item>,
 }
 
@@ -23,10 +39,21 @@ impl List {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Item {
     pub id: String,
     pub name: String,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
     pub list: Option<List>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Metadata::is_empty"))]
+    pub metadata: Metadata,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_false"))]
+    pub done: bool,
+}
+
+#[cfg(feature = "serde")]
+fn is_false(value: &bool) -> bool {
+    !value
 }
 
 impl Item {
@@ -35,6 +62,8 @@ impl Item {
             id: id,
             name: name,
             list: list,
+            metadata: Metadata::new(),
+            done: false,
         }
     }
 }
@@ -52,14 +81,53 @@ impl UI {
         self.app.options.page_options = page_options
     }
 
-    pub fn on_save(&mut self, handler: Box<dyn Fn(List) -> Option<String>>) {
-        let h = Box::new(move |lists: Vec<InternList>| {
-            let root = &lists[0];
-            let items = items_to_user(&lists, &root.items);
-            handler(List::new(root.name.clone(), items))
-        });
+    /// Sets how ids are generated for items added in the UI. Defaults to
+    /// `id::name()`. Generated ids are made unique within the tree by
+    /// appending a numeric suffix when needed.
+    pub fn set_id_generator(&mut self, generator: id::Generator) {
+        self.app.register_id_generator(generator);
+    }
+
+    /// Sets how many changes can be undone. Defaults to 100.
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.app.set_history_depth(depth);
+    }
+
+    /// Reads keys from and draws to /dev/tty instead of stdin and stdout,
+    /// leaving those free to be piped.
+    pub fn use_tty(&mut self, use_tty: bool) {
+        self.app.options.use_tty = use_tty;
+    }
+
+    /// Saves unsaved changes at most once per `interval` while the UI runs.
+    /// Off by default.
+    pub fn set_autosave(&mut self, interval: Option<Duration>) {
+        self.app.options.autosave = interval;
+    }
+
+    /// Sets how the tree is shown, `v` switches between the modes while the
+    /// UI runs. Defaults to `ViewMode::DrillDown`.
+    pub fn set_view_mode(&mut self, view_mode: ViewMode) {
+        self.app.options.view_mode = view_mode;
+    }
+
+    /// The tree as it currently is in the UI.
+    pub fn list(&self) -> List {
+        self.app.user_list()
+    }
 
-        self.app.register_save_handler(h);
+    /// Called with a copy of the tree when it is saved. The handler runs on
+    /// its own thread so a slow save doesn't hold up the UI, and saves asked
+    /// for while one is running are combined into a single save of the
+    /// latest tree. The returned notification is shown in the title bar, and
+    /// an error keeps the changes marked unsaved.
+    pub fn on_save(&mut self, handler: Box<dyn Fn(List) -> Option<Notification> + Send + Sync>) {
+        self.app.register_save_handler(Arc::from(handler));
+    }
+
+    /// Called with each edit as it is made, see `Change`.
+    pub fn on_change(&mut self, handler: Box<dyn Fn(Change)>) {
+        self.app.register_change_handler(handler);
     }
 
     pub fn run(&mut self) -> Result<(), failure::Error> {
@@ -75,18 +143,19 @@ fn init_app(list: List) -> App {
     let root = InternList::new(list.name);
     let mut app = App::new(root);
 
-    for item in items_from_user(&mut app, 0, &list.items) {
-        app.lists[0].items.push(item);
+    let root_index = app.root;
+    for item in items_from_user(&mut app, root_index, &list.items) {
+        app.lists[root_index].items.push(item);
     }
 
-    if app.lists[0].items.len() > 0 {
-        app.lists[0].selected = Some(0);
+    if app.lists[root_index].items.len() > 0 {
+        app.lists[root_index].selected = Some(0);
     }
 
     app
 }
 
-fn items_from_user(app: &mut App, current_list_index: usize, user_items: &Vec<Item>) -> Vec<InternItem> {
+fn items_from_user(app: &mut App, current_list_index: Index, user_items: &Vec<Item>) -> Vec<InternItem> {
     if user_items.len() == 0 {
         return Vec::new();
     }
@@ -96,6 +165,8 @@ fn items_from_user(app: &mut App, current_list_index: usize, user_items: &Vec<It
             user_item.id.clone(),
             user_item.name.clone(),
         );
+        item.metadata = user_item.metadata.clone();
+        item.done = user_item.done;
 
         match &user_item.list {
             Some(next_user_list) => {
@@ -119,34 +190,70 @@ fn items_from_user(app: &mut App, current_list_index: usize, user_items: &Vec<It
     }).collect()
 }
 
-fn items_to_user(lists: &Vec<InternList>, items: &Vec<InternItem>) -> Vec<Item> {
-    if items.len() == 0 {
-        return Vec::new();
-    }
-
-    return items.iter().map(|item| {
-        let mut user_item = Item::new(
-            item.id.clone(),
-            item.name.clone(),
-            None,
-        );
+fn items_to_user(lists: &Arena<InternList>, items: &Vec<InternItem>) -> Vec<Item> {
+    items.iter().map(|item| item_to_user(lists, item)).collect()
+}
 
-        match item.list_index {
-            Some(index) => {
-                let mut next_user_list = List::new(
-                    lists[index].name.clone(),
-                    Vec::new(),
-                );
+fn item_to_user(lists: &Arena<InternList>, item: &InternItem) -> Item {
+    let mut user_item = Item::new(
+        item.id.clone(),
+        item.name.clone(),
+        None,
+    );
+    user_item.metadata = item.metadata.clone();
+    user_item.done = item.done;
 
-                for next_user_item in items_to_user(lists, &lists[index].items) {
-                    next_user_list.items.push(next_user_item);
-                }
+    match item.list_index {
+        Some(index) => {
+            let mut next_user_list = List::new(
+                lists[index].name.clone(),
+                Vec::new(),
+            );
 
-                user_item.list = Some(next_user_list);
+            for next_user_item in items_to_user(lists, &lists[index].items) {
+                next_user_list.items.push(next_user_item);
             }
-            None => {}
+
+            user_item.list = Some(next_user_list);
         }
+        None => {}
+    }
+
+    user_item
+}
 
-        user_item
-    }).collect();
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_out_empty_fields_and_defaults_them_when_read() {
+        let list = List::new(String::from("root"), vec![
+            Item::new(String::from("a"), String::from("a"), None),
+        ]);
+        let json = serde_json::to_string(&list).unwrap();
+
+        assert_eq!(json, r#"{"name":"root","items":[{"id":"a","name":"a"}]}"#);
+
+        let read: List = serde_json::from_str(&json).unwrap();
+        assert!(read.items[0].list.is_none());
+        assert!(read.items[0].metadata.is_empty());
+        assert!(!read.items[0].done);
+    }
+
+    #[test]
+    fn keeps_metadata_in_order_and_done() {
+        let mut item = Item::new(String::from("a"), String::from("a"), None);
+        item.metadata.insert(String::from("z"), String::from("1"));
+        item.metadata.insert(String::from("b"), String::from("2"));
+        item.done = true;
+        let json = serde_json::to_string(&item).unwrap();
+
+        assert_eq!(json, r#"{"id":"a","name":"a","metadata":{"z":"1","b":"2"},"done":true}"#);
+
+        let read: Item = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.metadata.keys().collect::<Vec<_>>(), ["z", "b"]);
+        assert!(read.done);
+    }
 }
+
@@ -1,9 +1,25 @@
+use indexmap::IndexMap;
+
+use crate::arena::Index;
+
+/// An item's key/value fields, in the order they were added.
+pub type Metadata = IndexMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Position {
+    Before,
+    After,
+    End,
+}
+
 #[derive(Debug, Clone)]
 pub struct List{
     pub name: String,
     pub items: Vec<Item>,
     pub selected: Option<usize>,
-    pub previous: Option<usize>,
+    pub previous: Option<Index>,
+    // shown below its item in the outline view
+    pub expanded: bool,
 }
 
 impl List{
@@ -13,6 +29,7 @@ impl List{
             items: Vec::new(),
             selected: None,
             previous: None,
+            expanded: false,
         }
     }
 
@@ -33,7 +50,7 @@ impl List{
         self.selected = index;
     }
 
-    pub fn set_selected_item_list_index(&mut self, list_index: Option<usize>) {
+    pub fn set_selected_item_list_index(&mut self, list_index: Option<Index>) {
         match self.selected {
             Some(selected_item_index) => {
                 self.items[selected_item_index].list_index = list_index;
@@ -42,6 +59,70 @@ impl List{
         }
     }
 
+    pub fn set_selected_item_name(&mut self, name: String) {
+        if let Some(selected_item_index) = self.selected {
+            self.items[selected_item_index].name = name;
+        }
+    }
+
+    pub fn toggle_selected_item_done(&mut self) {
+        if let Some(selected_item_index) = self.selected {
+            let item = &mut self.items[selected_item_index];
+            item.done = !item.done;
+        }
+    }
+
+    /// Sets a metadata field on the selected item, an empty value removes it.
+    pub fn set_selected_item_metadata(&mut self, key: String, value: String) {
+        if let Some(selected_item_index) = self.selected {
+            let metadata = &mut self.items[selected_item_index].metadata;
+
+            if value.is_empty() {
+                metadata.shift_remove(&key);
+            } else {
+                metadata.insert(key, value);
+            }
+        }
+    }
+
+    /// Inserts an item relative to the selection and selects it. Without a
+    /// selection the item is appended.
+    pub fn insert_item(&mut self, item: Item, position: Position) {
+        let index = match (position, self.selected) {
+            (Position::Before, Some(selected)) => selected,
+            (Position::After, Some(selected)) => selected + 1,
+            _ => self.items.len(),
+        };
+
+        self.items.insert(index, item);
+        self.selected = Some(index);
+    }
+
+    /// Moves the selected item to `index`, keeping it selected.
+    pub fn move_selected_item(&mut self, index: usize) {
+        if let Some(selected) = self.selected {
+            let index = index.min(self.items.len() - 1);
+            let item = self.items.remove(selected);
+
+            self.items.insert(index, item);
+            self.selected = Some(index);
+        }
+    }
+
+    /// Removes the selected item and returns it, selecting its neighbour.
+    pub fn take_selected_item(&mut self) -> Option<Item> {
+        let selected = self.selected?;
+        let item = self.items.remove(selected);
+
+        self.selected = if self.items.is_empty() {
+            None
+        } else {
+            Some(selected.min(self.items.len() - 1))
+        };
+
+        Some(item)
+    }
+
     pub fn remove_selected_item(&mut self) {
         match self.selected {
             Some(selected) => {
@@ -98,7 +179,9 @@ impl List{
 pub struct Item {
     pub id: String,
     pub name: String,
-    pub list_index: Option<usize>,
+    pub list_index: Option<Index>,
+    pub metadata: Metadata,
+    pub done: bool,
 }
 
 impl Item{
@@ -107,6 +190,8 @@ impl Item{
             id: id,
             name: name.clone(),
             list_index: None,
+            metadata: Metadata::new(),
+            done: false,
         }
     }
 }
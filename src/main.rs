@@ -1,55 +1,164 @@
 extern crate nltui;
 
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use nltui::format::{markdown, opml, text, toml, yaml};
+use nltui::Notification;
+
+enum Format {
+    Json,
+    Markdown,
+    Opml,
+    Yaml,
+    Toml,
+    Text,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("md") | Some("markdown") => Format::Markdown,
+            Some("opml") => Format::Opml,
+            Some("yaml") | Some("yml") => Format::Yaml,
+            Some("toml") => Format::Toml,
+            Some("txt") => Format::Text,
+            _ => Format::Json,
+        }
+    }
+
+    fn parse(&self, contents: &str) -> Result<nltui::List, failure::Error> {
+        match self {
+            Format::Json => Ok(serde_json::from_str(contents)?),
+            Format::Markdown => Ok(markdown::from_str(contents)),
+            Format::Opml => Ok(opml::from_str(contents)?),
+            Format::Yaml => Ok(yaml::from_str(contents)?),
+            Format::Toml => Ok(toml::from_str(contents)?),
+            Format::Text => Ok(text::from_str(contents)),
+        }
+    }
+
+    fn render(&self, list: &nltui::List) -> Result<String, failure::Error> {
+        match self {
+            Format::Json => Ok(serde_json::to_string_pretty(list)?),
+            Format::Markdown => Ok(markdown::to_string(list)),
+            Format::Opml => Ok(opml::to_string(list)),
+            Format::Yaml => Ok(yaml::to_string(list)?),
+            Format::Toml => Ok(toml::to_string(list)?),
+            Format::Text => Ok(text::to_string(list)),
+        }
+    }
+}
+
 fn main() -> Result<(), failure::Error> {
-    let list = nltui::List{
-        name: String::from("first list"),
-        items: vec![
-            nltui::Item{
-                id: String::from("item 1 for first list"),
-                name: String::from("item 1 for first list"),
-                list: Some(nltui::List{
-                    name: String::from("second list"),
-                    items: vec![
-                        nltui::Item{
-                            id: String::from("item 1 for second list"),
-                            name: String::from("item 1 for second list"),
-                            list: Some(nltui::List{
-                                name: String::from("third list"),
-                                items: vec![
-                                    nltui::Item{
-                                        id: String::from("item 1 for third list"),
-                                        name: String::from("item 1 for third list"),
-                                        list: None,
-                                    },
-                                ],
-                            }),
-                        },
-                    ],
-                }),
-            },
-            nltui::Item{
-                id: String::from("item 2 for first list"),
-                name: String::from("item 2 for first list"),
-                list: None,
-            },
-        ]
-    };
+    match env::args().nth(1) {
+        Some(ref path) if path == "-" => edit_stdin(),
+        Some(path) => edit_file(PathBuf::from(path)),
+        None if !termion::is_tty(&io::stdin()) => edit_stdin(),
+        None => {
+            eprintln!("usage: nltui <file>");
+            eprintln!("       nltui - < list.txt > edited.txt");
+            process::exit(1);
+        }
+    }
+}
 
-    let mut page_options = vec![
-        nltui::PageOptions::new(String::from("Example1")),
-        nltui::PageOptions::new(String::from("Example2")),
-        nltui::PageOptions::new(String::from("Example3")),
-    ];
+// reads an indented text tree from stdin, edits it on the terminal and
+// prints the result to stdout
+fn edit_stdin() -> Result<(), failure::Error> {
+    let mut contents = String::new();
+    io::stdin().read_to_string(&mut contents)?;
 
-    page_options[0].disable_delete = true;
-    page_options[2].disable_add = true;
+    let mut list = text::from_str(&contents);
+    list.name = String::from("stdin");
+
+    // what is printed on exit, changes that were discarded are left out
+    let saved = Arc::new(Mutex::new(list.clone()));
 
     let mut ui = nltui::UI::new(list);
-    ui.set_page_options(page_options);
+    ui.use_tty(true);
 
-    ui.on_save(Box::new(|_list: nltui::List| {
-        Some(String::from("SAVED!"))
+    let on_save = Arc::clone(&saved);
+    ui.on_save(Box::new(move |list: nltui::List| {
+        *on_save.lock().unwrap() = list;
+        Some(Notification::success(String::from("Saved, the list is written to stdout on exit")))
+    }));
+
+    ui.run()?;
+
+    print!("{}", text::to_string(&saved.lock().unwrap()));
+    Ok(())
+}
+
+fn edit_file(path: PathBuf) -> Result<(), failure::Error> {
+    // lines of the file that saving would drop
+    let mut unsupported_lines = Vec::new();
+
+    let list = if path.exists() {
+        let contents = fs::read_to_string(&path)?;
+        let format = Format::from_path(&path);
+        if let Format::Markdown = format {
+            unsupported_lines = markdown::unsupported_lines(&contents);
+        }
+
+        let mut list = format.parse(&contents)?;
+        if list.name.is_empty() {
+            list.name = default_list_name(&path);
+        }
+        list
+    } else {
+        let list = nltui::List::new(default_list_name(&path), Vec::new());
+        write_list(&path, &list)?;
+        list
+    };
+
+    let mut ui = nltui::UI::new(list);
+
+    // the first save refuses to drop anything, saving again goes ahead
+    let warned = AtomicBool::new(unsupported_lines.is_empty());
+
+    ui.on_save(Box::new(move |list: nltui::List| {
+        if !warned.swap(true, Ordering::SeqCst) {
+            return Some(Notification::error(format!(
+                "Not saved, {} has text besides headings and bullets from line {}. W again drops it",
+                path.display(), unsupported_lines[0],
+            )));
+        }
+
+        match write_list(&path, &list) {
+            Ok(()) => Some(Notification::success(format!("Saved {}", path.display()))),
+            Err(err) => Some(Notification::error(format!("Failed to save {}: {}", path.display(), err))),
+        }
     }));
 
     ui.run()
 }
+
+fn default_list_name(path: &Path) -> String {
+    match path.file_stem() {
+        Some(stem) => stem.to_string_lossy().into_owned(),
+        None => String::from("list"),
+    }
+}
+
+// writes to a temporary file next to `path` and renames it into place, so
+// a failed save never leaves a half written document behind
+fn write_list(path: &Path, list: &nltui::List) -> Result<(), failure::Error> {
+    let contents = Format::from_path(path).render(list)?;
+
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
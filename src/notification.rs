@@ -0,0 +1,42 @@
+/// How a notification is styled in the title bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// A message shown in the title bar until it times out, such as the result
+/// of a save.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub level: Level,
+    pub message: String,
+}
+
+impl Notification {
+    pub fn new(level: Level, message: String) -> Notification {
+        Notification{level, message}
+    }
+
+    pub fn info(message: String) -> Notification {
+        Notification::new(Level::Info, message)
+    }
+
+    pub fn success(message: String) -> Notification {
+        Notification::new(Level::Success, message)
+    }
+
+    pub fn warning(message: String) -> Notification {
+        Notification::new(Level::Warning, message)
+    }
+
+    pub fn error(message: String) -> Notification {
+        Notification::new(Level::Error, message)
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.level == Level::Error
+    }
+}
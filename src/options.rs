@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 #[derive(Debug, Clone)]
 pub struct PageOptions{
     pub title: String,
@@ -27,14 +29,31 @@ impl PageOptions{
     }
 }
 
+/// How the tree is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewMode {
+    /// One list at a time, entering and leaving nested lists.
+    DrillDown,
+    /// The whole tree at once, nested lists expanding and collapsing in
+    /// place.
+    Outline,
+}
+
 pub struct Options{
     pub page_options: Vec<PageOptions>,
+    pub use_tty: bool,
+    // how often unsaved changes are saved, checked on every tick
+    pub autosave: Option<Duration>,
+    pub view_mode: ViewMode,
 }
 
 impl Options{
     pub fn new() -> Options {
         Options{
             page_options: Vec::new(),
+            use_tty: false,
+            autosave: None,
+            view_mode: ViewMode::DrillDown,
         }
     }
 }
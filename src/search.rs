@@ -0,0 +1,262 @@
+use std::cmp::Reverse;
+
+use crate::arena::{Arena, Index};
+use crate::list::{Item, List};
+
+/// Positions of the characters in `name` that match `query` as a substring,
+/// ignoring case. An empty query matches every name.
+pub fn find(name: &str, query: &str) -> Option<Vec<usize>> {
+    let name: Vec<char> = name.chars().collect();
+    let query: Vec<char> = query.chars().collect();
+
+    if query.len() > name.len() {
+        return None;
+    }
+
+    (0..=name.len() - query.len())
+        .find(|&start| {
+            query.iter().enumerate().all(|(offset, c)| same_char(name[start + offset], *c))
+        })
+        .map(|start| (start..start + query.len()).collect())
+}
+
+fn same_char(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Narrows a list to the items whose names match a query, leaving the list
+/// itself untouched. Matches refer to items by their index in `List::items`.
+pub struct Filter {
+    pub query: String,
+    // the index of each matching item, along with the matched characters
+    pub matches: Vec<(usize, Vec<usize>)>,
+    // position in `matches`
+    pub selected: Option<usize>,
+    // what was selected in the list before filtering, to go back to when
+    // the filter is cancelled
+    pub previous_selected: Option<usize>,
+}
+
+impl Filter {
+    pub fn new(items: &[Item], previous_selected: Option<usize>) -> Filter {
+        let mut filter = Filter{
+            query: String::new(),
+            matches: Vec::new(),
+            selected: None,
+            previous_selected,
+        };
+        filter.update(items);
+        filter
+    }
+
+    /// Finds the matches for the current query, keeping the selected item if
+    /// it still matches.
+    pub fn update(&mut self, items: &[Item]) {
+        let selected_item = self.get_selected_item_index().or(self.previous_selected);
+
+        self.matches = items.iter()
+            .enumerate()
+            .filter_map(|(index, item)| find(&item.name, &self.query).map(|positions| (index, positions)))
+            .collect();
+
+        self.selected = match selected_item {
+            Some(item_index) => self.matches.iter().position(|(index, _)| *index == item_index),
+            None => None,
+        };
+
+        if self.selected.is_none() && !self.matches.is_empty() {
+            self.selected = Some(0);
+        }
+    }
+
+    /// The index in `List::items` of the selected match.
+    pub fn get_selected_item_index(&self) -> Option<usize> {
+        self.selected.map(|selected| self.matches[selected].0)
+    }
+
+    pub fn select_next(&mut self) {
+        if let Some(selected) = self.selected {
+            self.selected = Some((selected + 1) % self.matches.len());
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if let Some(selected) = self.selected {
+            self.selected = Some(selected.checked_sub(1).unwrap_or(self.matches.len() - 1));
+        }
+    }
+}
+
+/// Scores `name` against `query` when the query's characters appear in the
+/// name in order, though not necessarily next to each other, ignoring case.
+/// Runs of characters and characters that start a word score higher. Returns
+/// the score along with the positions of the matched characters.
+pub fn fuzzy(name: &str, query: &str) -> Option<(i64, Vec<usize>)> {
+    let name: Vec<char> = name.chars().collect();
+    let mut positions: Vec<usize> = Vec::new();
+    let mut score = 0;
+
+    for c in query.chars() {
+        let start = positions.last().map_or(0, |last| last + 1);
+        let position = start + name.get(start..)?.iter().position(|n| same_char(*n, c))?;
+
+        score += 10;
+        if position > 0 && positions.last() == Some(&(position - 1)) {
+            score += 15;
+        }
+        if position == 0 || !name[position - 1].is_alphanumeric() {
+            score += 10;
+        }
+
+        positions.push(position);
+    }
+
+    // prefer matches that start early and leave less of the name unmatched
+    if let Some(first) = positions.first() {
+        score -= *first as i64;
+    }
+    score -= (name.len() - positions.len()) as i64 / 4;
+
+    Some((score, positions))
+}
+
+/// An item found by `search`.
+pub struct SearchResult {
+    // the list and position of each item from the root down to the match
+    pub trail: Vec<(Index, usize)>,
+    // the names of the root list and the items above the match
+    pub breadcrumb: Vec<String>,
+    pub name: String,
+    // the matched characters of the name
+    pub positions: Vec<usize>,
+    pub score: i64,
+}
+
+/// Ranks every item in the tree below `root` against a fuzzy query, best
+/// match first. Items that score the same stay in tree order.
+pub fn search(lists: &Arena<List>, root: Index, query: &str) -> Vec<SearchResult> {
+    let mut results = Vec::new();
+    let breadcrumb = vec![lists[root].name.clone()];
+
+    search_list(lists, root, query, &mut Vec::new(), &breadcrumb, &mut results);
+
+    results.sort_by_key(|result| Reverse(result.score));
+    results
+}
+
+fn search_list(
+    lists: &Arena<List>,
+    list_index: Index,
+    query: &str,
+    trail: &mut Vec<(Index, usize)>,
+    breadcrumb: &[String],
+    results: &mut Vec<SearchResult>,
+) {
+    for (position, item) in lists[list_index].items.iter().enumerate() {
+        trail.push((list_index, position));
+
+        if let Some((score, positions)) = fuzzy(&item.name, query) {
+            results.push(SearchResult{
+                trail: trail.clone(),
+                breadcrumb: breadcrumb.to_vec(),
+                name: item.name.clone(),
+                positions,
+                score,
+            });
+        }
+
+        if let Some(nested_index) = item.list_index {
+            let mut nested_breadcrumb = breadcrumb.to_vec();
+            nested_breadcrumb.push(item.name.clone());
+            search_list(lists, nested_index, query, trail, &nested_breadcrumb, results);
+        }
+
+        trail.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(names: &[&str]) -> Vec<Item> {
+        names.iter().map(|name| Item::new(name.to_string(), name.to_string())).collect()
+    }
+
+    #[test]
+    fn finds_substrings_ignoring_case() {
+        assert_eq!(find("Buy Milk", "milk"), Some(vec![4, 5, 6, 7]));
+        assert_eq!(find("Straße", "SSE"), None);
+        assert_eq!(find("Crème", "ÈM"), Some(vec![2, 3]));
+        assert_eq!(find("milk", "milks"), None);
+        assert_eq!(find("milk", ""), Some(vec![]));
+        assert_eq!(find("", ""), Some(vec![]));
+    }
+
+    #[test]
+    fn filters_and_keeps_the_selected_match() {
+        let items = items(&["apple", "banana", "grape", "cherry"]);
+        let mut filter = Filter::new(&items, Some(2));
+
+        assert_eq!(filter.matches.len(), 4);
+        assert_eq!(filter.get_selected_item_index(), Some(2));
+
+        filter.query = String::from("ap");
+        filter.update(&items);
+        assert_eq!(filter.matches.iter().map(|(index, _)| *index).collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(filter.get_selected_item_index(), Some(2));
+
+        filter.select_next();
+        assert_eq!(filter.get_selected_item_index(), Some(0));
+        filter.select_previous();
+        assert_eq!(filter.get_selected_item_index(), Some(2));
+
+        filter.query = String::from("an");
+        filter.update(&items);
+        assert_eq!(filter.get_selected_item_index(), Some(1));
+
+        filter.query = String::from("x");
+        filter.update(&items);
+        assert_eq!(filter.get_selected_item_index(), None);
+        filter.select_next();
+        assert_eq!(filter.selected, None);
+    }
+
+    #[test]
+    fn matches_characters_in_order() {
+        assert_eq!(fuzzy("groceries", "gcs").map(|(_, positions)| positions), Some(vec![0, 3, 8]));
+        assert_eq!(fuzzy("groceries", "GRO").map(|(_, positions)| positions), Some(vec![0, 1, 2]));
+        assert_eq!(fuzzy("groceries", "sg"), None);
+        assert_eq!(fuzzy("ab", "abc"), None);
+    }
+
+    #[test]
+    fn scores_runs_and_word_starts_higher() {
+        let score = |name: &str, query: &str| fuzzy(name, query).unwrap().0;
+
+        assert!(score("milk", "mil") > score("my ill", "mil"));
+        assert!(score("buy milk", "m") > score("hummus", "m"));
+        assert!(score("milk", "milk") > score("milkshake", "milk"));
+    }
+
+    #[test]
+    fn searches_the_whole_tree_best_match_first() {
+        let mut lists = Arena::new();
+        let root = lists.insert(List::new(String::from("root")));
+        let nested = lists.insert(List::new(String::from("Chores")));
+
+        lists[root].items = items(&["Chores", "Read mail"]);
+        lists[root].items[0].list_index = Some(nested);
+        lists[nested].items = items(&["Mail letters", "Mop"]);
+
+        let results = search(&lists, root, "mail");
+
+        assert_eq!(results.iter().map(|result| result.name.as_str()).collect::<Vec<_>>(), ["Mail letters", "Read mail"]);
+        assert_eq!(results[0].trail, vec![(root, 0), (nested, 0)]);
+        assert_eq!(results[0].breadcrumb, ["root", "Chores"]);
+        assert_eq!(results[0].positions, [0, 1, 2, 3]);
+        assert_eq!(results[1].trail, vec![(root, 1)]);
+        assert_eq!(results[1].breadcrumb, ["root"]);
+        assert_eq!(search(&lists, root, "").len(), 4);
+    }
+}
@@ -1,68 +1,168 @@
 use std::io;
-use std::sync::mpsc;
-use std::thread;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
-use termion::event::Key;
-use termion::input::TermRead;
+use termion::event::{self, Key};
+
+use crate::notification::Notification;
 
 pub enum Event<I> {
     Input(I),
+    Tick,
+    // a save running in the background finished
+    Saved(Option<Notification>),
 }
 
 /// A small event handler that wrap termion input and tick events. Each event
-/// type is handled in its own thread and returned to a common `Receiver`
+/// type is handled in its own thread and returned to a common `Receiver`.
+/// Input stops being read once dropped, leaving any later keys to whatever
+/// reads the terminal next.
 pub struct Events {
     rx: mpsc::Receiver<Event<Key>>,
+    tx: mpsc::Sender<Event<Key>>,
+    stop: Arc<AtomicBool>,
+    input_handle: Option<JoinHandle<()>>,
 }
 
+// how long the input thread waits for a key before checking whether to stop
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 #[derive(Debug, Clone, Copy)]
 pub struct Config {
-    pub exit_key: Key,
     pub tick_rate: Duration,
+    // read keys from /dev/tty instead of stdin
+    pub tty: bool,
 }
 
 impl Default for Config {
     fn default() -> Config {
         Config {
-            exit_key: Key::Ctrl('c'),
             tick_rate: Duration::from_millis(250),
+            tty: false,
         }
     }
 }
 
 impl Events {
-    pub fn new() -> Events {
-        Events::with_config(Config::default())
-    }
-
-    pub fn with_config(config: Config) -> Events {
+    /// Fails if `config.tty` is set and /dev/tty can't be opened.
+    pub fn with_config(config: Config) -> io::Result<Events> {
         let (tx, rx) = mpsc::channel();
-        {
+        let stop = Arc::new(AtomicBool::new(false));
+        // kept open by the input thread for as long as keys are read from it
+        let tty = if config.tty {
+            Some(termion::get_tty()?)
+        } else {
+            None
+        };
+        let input_handle = {
             let tx = tx.clone();
+            let stop = Arc::clone(&stop);
             thread::spawn(move || {
-                let stdin = io::stdin();
-                for evt in stdin.keys() {
-                    match evt {
-                        Ok(key) => {
-                            if let Err(_) = tx.send(Event::Input(key)) {
-                                return;
-                            }
-                            if key == config.exit_key {
-                                return;
-                            }
+                let fd = tty.as_ref().map_or(libc::STDIN_FILENO, |tty| tty.as_raw_fd());
+
+                // only reads once a key is waiting, so that no key is taken
+                // after being told to stop
+                while !stop.load(Ordering::SeqCst) {
+                    if !wait_for_input(fd, INPUT_POLL_INTERVAL) {
+                        continue;
+                    }
+
+                    let keys = match read_keys(fd) {
+                        Ok(Some(keys)) => keys,
+                        Ok(None) => return,
+                        Err(ref err) if is_transient(err) => continue,
+                        Err(_) => return,
+                    };
+
+                    for key in keys {
+                        if let Err(_) = tx.send(Event::Input(key)) {
+                            return;
                         }
-                        Err(_) => {}
                     }
                 }
             })
         };
-        Events {
-            rx,
+        {
+            let tx = tx.clone();
+            thread::spawn(move || loop {
+                if tx.send(Event::Tick).is_err() {
+                    break;
+                }
+                thread::sleep(config.tick_rate);
+            });
         }
+        Ok(Events {
+            rx,
+            tx,
+            stop,
+            input_handle: Some(input_handle),
+        })
+    }
+
+    /// For sending events from other threads, such as a finished save.
+    pub fn sender(&self) -> mpsc::Sender<Event<Key>> {
+        self.tx.clone()
     }
 
     pub fn next(&self) -> Result<Event<Key>, mpsc::RecvError> {
         self.rx.recv()
     }
 }
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+
+        if let Some(handle) = self.input_handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+// whether input arrived on `fd` within `timeout`
+fn wait_for_input(fd: RawFd, timeout: Duration) -> bool {
+    let mut poll_fd = libc::pollfd{
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+
+    unsafe { libc::poll(&mut poll_fd, 1, timeout.as_millis() as libc::c_int) > 0 }
+}
+
+// whether reading again might succeed
+fn is_transient(err: &io::Error) -> bool {
+    matches!(err.kind(), io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock)
+}
+
+// reads everything waiting on `fd`, unbuffered so that nothing read is left
+// behind once the thread stops. None at the end of input
+fn read_keys(fd: RawFd) -> io::Result<Option<Vec<Key>>> {
+    let mut buffer = [0u8; 1024];
+    let read = unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+
+    if read < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if read == 0 {
+        return Ok(None);
+    }
+
+    let mut bytes = buffer[..read as usize].iter().map(|byte| Ok(*byte));
+    let mut keys = Vec::new();
+
+    while let Some(Ok(byte)) = bytes.next() {
+        // an escape with nothing after it is the escape key rather than the
+        // start of a sequence
+        if byte == b'\x1B' && bytes.len() == 0 {
+            keys.push(Key::Esc);
+        } else if let Ok(event::Event::Key(key)) = event::parse_event(byte, &mut bytes) {
+            keys.push(key);
+        }
+    }
+
+    Ok(Some(keys))
+}
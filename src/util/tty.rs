@@ -0,0 +1,40 @@
+use std::io::{self, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+
+/// Points stdout at the controlling terminal until dropped, so the UI can
+/// draw while the process' real stdout is redirected. termion always puts
+/// file descriptor 1 into raw mode and reads the terminal size from it.
+pub struct StdoutToTty {
+    saved: RawFd,
+}
+
+impl StdoutToTty {
+    pub fn new() -> io::Result<StdoutToTty> {
+        let tty = termion::get_tty()?;
+        io::stdout().flush()?;
+
+        let saved = unsafe { libc::dup(libc::STDOUT_FILENO) };
+        if saved < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        if unsafe { libc::dup2(tty.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+            let err = io::Error::last_os_error();
+            unsafe { libc::close(saved) };
+            return Err(err);
+        }
+
+        Ok(StdoutToTty{saved})
+    }
+}
+
+impl Drop for StdoutToTty {
+    fn drop(&mut self) {
+        io::stdout().flush().ok();
+
+        unsafe {
+            libc::dup2(self.saved, libc::STDOUT_FILENO);
+            libc::close(self.saved);
+        }
+    }
+}